@@ -0,0 +1,94 @@
+// --- Base Class Accessor Surface ---
+// The generated getters call accessor helpers (GetString, GetBool, ...) inherited from the
+// base class. Their names and signatures differ between Sharpliner versions and custom base
+// classes, so they can be read from the base class source instead of assumed.
+
+use regex::Regex;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    // Matches accessor method declarations, e.g.
+    // 'protected string? GetString(string name, string? defaultValue = null)'
+    static ref ACCESSOR_DECL_RE: Regex = Regex::new(
+        r"(?:public|protected|internal)\s+(?:static\s+|virtual\s+)*[\w<>?\[\],\s]*?\s(?<Name>Get\w+)\s*(?:<[^>(]*>)?\s*\((?<Params>[^)]*)\)"
+    ).expect("Invalid Accessor Declaration Regex");
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessorKind {
+    String,
+    Bool,
+    Int,
+    Enum,
+    NullableEnum,
+    SecureFile,
+    ServiceConnection,
+}
+
+impl AccessorKind {
+    // Accessor names that can serve this kind, in order of preference
+    fn candidates(self) -> &'static [&'static str] {
+        match self {
+            AccessorKind::String => &["GetString"],
+            AccessorKind::Bool => &["GetBool", "GetBoolean"],
+            AccessorKind::Int => &["GetInt", "GetInt32", "GetInteger"],
+            AccessorKind::Enum => &["GetEnum"],
+            AccessorKind::NullableEnum => &["GetNullableEnum"],
+            // Base classes without a dedicated secure file accessor read them as strings
+            AccessorKind::SecureFile => &["GetSecureFile", "GetString"],
+            AccessorKind::ServiceConnection => &["GetServiceConnection", "GetString"],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Accessor {
+    pub name: String,
+    pub takes_default: bool, // Whether any overload accepts a default value argument
+}
+
+pub struct AccessorSurface {
+    accessors: Vec<Accessor>,
+    pub verified: bool, // True when read from actual base class source
+}
+
+impl AccessorSurface {
+    // The accessor surface the generator has always assumed
+    pub fn sharpliner_default() -> Self {
+        let accessor = |name: &str, takes_default| Accessor { name: name.to_string(), takes_default };
+        AccessorSurface {
+            accessors: vec![
+                accessor("GetString", true),
+                accessor("GetBool", true),
+                accessor("GetInt", true),
+                accessor("GetEnum", true),
+                accessor("GetNullableEnum", false),
+            ],
+            verified: false,
+        }
+    }
+
+    // Reads the accessor methods declared in C# base class source
+    pub fn parse_csharp(source: &str) -> Self {
+        let mut accessors: Vec<Accessor> = Vec::new();
+
+        for caps in ACCESSOR_DECL_RE.captures_iter(source) {
+            let name = caps["Name"].to_string();
+            let param_count = caps["Params"].split(',').filter(|p| !p.trim().is_empty()).count();
+            let takes_default = param_count >= 2;
+
+            // Overloads are merged into a single entry
+            match accessors.iter_mut().find(|a| a.name == name) {
+                Some(existing) => existing.takes_default |= takes_default,
+                None => accessors.push(Accessor { name, takes_default }),
+            }
+        }
+
+        AccessorSurface { accessors, verified: true }
+    }
+
+    pub fn resolve(&self, kind: AccessorKind) -> Option<&Accessor> {
+        kind.candidates().iter()
+            .find_map(|candidate| self.accessors.iter().find(|a| a.name == *candidate))
+    }
+}
@@ -0,0 +1,198 @@
+// --- Output Archives ---
+// Packages everything a run emitted, plus a summary of the run and a lockfile, into a single
+// .zip or .tar.gz so it can be published from CI or attached to a ticket. The lockfile records
+// where each task was read from and content hashes of its source and of every emitted file, so
+// an archive can be checked against a later run.
+
+use std::io::Write;
+use std::path::{Component, Path};
+
+use serde::Serialize;
+
+use crate::cache;
+use crate::errors::{Failure, FailureKind};
+use crate::TaskDefinition;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Lockfile<'a> {
+    generator: String,
+    tasks: Vec<LockedTask<'a>>,
+    files: Vec<LockedFile<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LockedTask<'a> {
+    task_name: &'a str,
+    task_version: &'a str,
+    class_name: &'a str,
+    source: &'a str,      // URL or path the task was read from
+    source_hash: &'a str, // Of the page or file, as read
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LockedFile<'a> {
+    name: &'a str,
+    hash: String,
+}
+
+// Archive entries keep an output's relative path, e.g. 'Enums/Command.cs', without any '.' or
+// '..' in it; only the file name of an absolute path is kept
+pub fn entry_name(path: &Path) -> String {
+    if path.is_absolute() {
+        return path.file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+    }
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            Component::ParentDir => { parts.pop(); }
+            _ => {}
+        }
+    }
+    parts.join("/")
+}
+
+// The lockfile, 'lock.json', of the tasks and the files emitted for them
+pub fn lockfile(tasks: &[TaskDefinition], emitted: &[(String, String)]) -> Result<String, serde_json::Error> {
+    let lockfile = Lockfile {
+        generator: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        tasks: tasks.iter().map(|t| LockedTask {
+            task_name: &t.info.task_name,
+            task_version: &t.info.task_version,
+            class_name: &t.class_name,
+            source: &t.documentation_url,
+            source_hash: &t.source_hash,
+        }).collect(),
+        files: emitted.iter().map(|(name, contents)| LockedFile { name, hash: cache::hash(contents) }).collect(),
+    };
+    serde_json::to_string_pretty(&lockfile)
+}
+
+// A plain text summary of what the run generated and what failed
+pub fn run_summary(tasks: &[TaskDefinition], emitted: &[(String, String)], failures: &[Failure]) -> String {
+    let mut summary = format!("Generated by {} version {}\n", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+
+    summary.push_str(&format!("\nTasks ({}):\n", tasks.len()));
+    for task in tasks {
+        summary.push_str(&format!(
+            "  {}@{} -> {} ({})\n",
+            task.info.task_name, task.info.task_version, task.class_name, task.documentation_url
+        ));
+    }
+
+    let deprecated: Vec<&TaskDefinition> = tasks.iter().filter(|t| t.deprecation.is_some()).collect();
+    if !deprecated.is_empty() {
+        summary.push_str(&format!("\nDeprecated ({}):\n", deprecated.len()));
+        for task in deprecated {
+            summary.push_str(&format!("  {}\n", deprecation_line(task)));
+        }
+    }
+
+    summary.push_str(&format!("\nFiles ({}):\n", emitted.len()));
+    for (name, _) in emitted {
+        summary.push_str(&format!("  {}\n", name));
+    }
+
+    if !failures.is_empty() {
+        summary.push_str(&format!("\nFailures ({}):\n", failures.len()));
+        for failure in failures {
+            summary.push_str(&format!("  {}\n", failure));
+        }
+    }
+
+    summary
+}
+
+// A deprecated task and its replacement, e.g. 'AzureFunctionApp@1 -> AzureFunctionApp@2'
+pub fn deprecation_line(task: &TaskDefinition) -> String {
+    let replacement = task.deprecation.as_ref()
+        .and_then(|d| d.replacement.as_ref())
+        .map(|r| format!(" -> {}@{}", r.task_name, r.task_version))
+        .unwrap_or_default();
+    format!("{}@{}{}", task.info.task_name, task.info.task_version, replacement)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+// The format of an archive, chosen by its extension; checked with the other usage errors, so
+// an unsupported one fails before anything is fetched
+pub fn archive_format(path: &Path) -> Option<ArchiveFormat> {
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if file_name.ends_with(".zip") {
+        Some(ArchiveFormat::Zip)
+    } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+        Some(ArchiveFormat::TarGz)
+    } else {
+        None
+    }
+}
+
+pub fn unsupported_format(path: &Path) -> Failure {
+    Failure::new(FailureKind::Usage, format!("Unsupported archive format for {}; use .zip or .tar.gz", path.display()))
+}
+
+// Writes the entries into a .zip or .tar.gz, chosen by the archive's extension
+pub fn write_archive(path: &Path, entries: &[(String, String)]) -> Result<(), Failure> {
+    let result = match archive_format(path) {
+        Some(ArchiveFormat::Zip) => write_zip(path, entries),
+        Some(ArchiveFormat::TarGz) => write_tar_gz(path, entries),
+        None => return Err(unsupported_format(path)),
+    };
+
+    result.map_err(|e| Failure::new(FailureKind::Emission, format!("Failed to write archive {}: {}", path.display(), e)))
+}
+
+// Entries get fixed timestamps so an unchanged run produces an identical archive
+fn write_zip(path: &Path, entries: &[(String, String)]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(path)?);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default());
+
+    for (name, contents) in entries {
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(contents.as_bytes())?;
+    }
+    zip.finish()?;
+
+    Ok(())
+}
+
+fn write_tar_gz(path: &Path, entries: &[(String, String)]) -> Result<(), Box<dyn std::error::Error>> {
+    let encoder = flate2::write::GzEncoder::new(std::fs::File::create(path)?, flate2::Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+
+    for (name, contents) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        tar.append_data(&mut header, name, contents.as_bytes())?;
+    }
+    tar.into_inner()?.finish()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_formats_follow_the_extension() {
+        assert_eq!(archive_format(Path::new("out/Tasks.ZIP")), Some(ArchiveFormat::Zip));
+        assert_eq!(archive_format(Path::new("Tasks.tar.gz")), Some(ArchiveFormat::TarGz));
+        assert_eq!(archive_format(Path::new("Tasks.tgz")), Some(ArchiveFormat::TarGz));
+        assert_eq!(archive_format(Path::new("Tasks.tar")), None);
+        assert_eq!(archive_format(Path::new("zip")), None);
+    }
+}
@@ -0,0 +1,52 @@
+// --- Sharpliner Built-in Task Index ---
+// Sharpliner already ships first-class models for a number of common tasks. Generating
+// a wrapper for one of those only produces a duplicate, so we keep an index of them and
+// warn when a requested task is in it.
+
+const BUNDLED_INDEX: &str = include_str!("sharpliner_tasks.txt");
+
+#[derive(Debug, Clone)]
+pub struct BuiltinTask {
+    pub task_name: String,
+    pub task_version: String,
+    pub class_name: String,
+}
+
+pub struct BuiltinTaskIndex {
+    entries: Vec<BuiltinTask>,
+}
+
+impl BuiltinTaskIndex {
+    // The index compiled into the tool
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_INDEX)
+    }
+
+    // Parses an index in the same format as 'sharpliner_tasks.txt':
+    // one '<TaskName>@<MajorVersion> <SharplinerClass>' entry per line, '#' for comments.
+    pub fn parse(text: &str) -> Self {
+        let entries = text.lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(|l| {
+                let mut parts = l.split_whitespace();
+                let (task_name, task_version) = parts.next()?.split_once('@')?;
+                let class_name = parts.next().unwrap_or("").to_string();
+                Some(BuiltinTask {
+                    task_name: task_name.to_string(),
+                    task_version: task_version.to_string(),
+                    class_name,
+                })
+            })
+            .collect();
+
+        BuiltinTaskIndex { entries }
+    }
+
+    // Azure DevOps treats task names case-insensitively, so we do too.
+    pub fn find(&self, task_name: &str, task_version: &str) -> Option<&BuiltinTask> {
+        self.entries.iter().find(|e| {
+            e.task_name.eq_ignore_ascii_case(task_name) && e.task_version == task_version
+        })
+    }
+}
@@ -0,0 +1,83 @@
+// --- On-Disk HTTP Cache ---
+// Fetched pages are kept in a cache directory, one file per URL, so repeated runs and batch
+// crawls don't fetch the same pages from learn.microsoft.com over and over. Each page's ETag
+// and Last-Modified are kept alongside it, so a stale copy can be revalidated instead of
+// downloaded again.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+// Response headers a stale copy is revalidated with
+#[derive(Debug, Clone, Default)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+pub struct CachedPage {
+    pub body: String,
+    pub validators: Validators,
+}
+
+// File a URL's response is cached in. The name is a hash of the URL, since URLs aren't valid
+// file names.
+pub fn entry_path(cache_dir: &Path, url: &str) -> PathBuf {
+    cache_dir.join(format!("{}.html", hash(url)))
+}
+
+// Hex FNV-1a hash of a text; FNV-1a is used because it's stable across Rust versions and platforms
+pub fn hash(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+// Validators are stored next to the page as 'ETag: ...' / 'Last-Modified: ...' lines
+fn validators_path(path: &Path) -> PathBuf {
+    path.with_extension("headers")
+}
+
+// The cached body, if it was cached (or last revalidated) less than 'ttl' ago
+pub fn read_fresh(path: &Path, ttl: Duration) -> Option<String> {
+    let age = std::fs::metadata(path).ok()?.modified().ok()?.elapsed().ok()?;
+    if age > ttl {
+        return None;
+    }
+    std::fs::read_to_string(path).ok()
+}
+
+// The cached page regardless of age, for revalidation
+pub fn read(path: &Path) -> Option<CachedPage> {
+    let body = std::fs::read_to_string(path).ok()?;
+    let mut validators = Validators::default();
+    for line in std::fs::read_to_string(validators_path(path)).unwrap_or_default().lines() {
+        match line.split_once(": ") {
+            Some(("ETag", value)) => validators.etag = Some(value.to_string()),
+            Some(("Last-Modified", value)) => validators.last_modified = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    Some(CachedPage { body, validators })
+}
+
+pub fn write(path: &Path, body: &str, validators: &Validators) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, body)?;
+
+    let mut headers = String::new();
+    if let Some(etag) = &validators.etag {
+        headers.push_str(&format!("ETag: {}\n", etag));
+    }
+    if let Some(last_modified) = &validators.last_modified {
+        headers.push_str(&format!("Last-Modified: {}\n", last_modified));
+    }
+    std::fs::write(validators_path(path), headers)
+}
+
+// Marks a revalidated page as fresh again
+pub fn touch(path: &Path) -> std::io::Result<()> {
+    std::fs::File::options().append(true).open(path)?.set_modified(SystemTime::now())
+}
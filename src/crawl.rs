@@ -0,0 +1,75 @@
+// --- Task Reference Crawling ---
+// Discovers every task page linked from the task reference index, so the whole catalog can be
+// regenerated in one run, and the pages of a task's other major versions. Page URLs are built
+// on the task reference base URL, which may be a mirror of learn.microsoft.com.
+
+use regex::Regex;
+use lazy_static::lazy_static;
+use scraper::{Html, Selector};
+
+pub const DEFAULT_TASK_REFERENCE_URL: &str = "https://learn.microsoft.com/en-us/azure/devops/pipelines/tasks/reference/";
+
+lazy_static! {
+    // Links to task pages, relative ('npm-v1?view=...') or absolute ('/en-us/.../tasks/reference/npm-v1')
+    static ref TASK_PAGE_LINK_RE: Regex = Regex::new(
+        r"^(?:\./)?(?:.*/tasks/reference/)?(?<Slug>[a-z0-9]+(?:-[a-z0-9]+)*-v\d+)/?(?:[?#].*)?$"
+    ).expect("Invalid Task Page Link Regex");
+}
+
+// The task reference index page
+pub fn index_url(base_url: &str) -> String {
+    format!("{}?view=azure-pipelines", base_url)
+}
+
+// The page of a task reference slug, e.g. 'npm-v1'
+pub fn page_url(base_url: &str, slug: &str) -> String {
+    format!("{}{}?view=azure-pipelines", base_url, slug)
+}
+
+// The task reference slug a link or URL points to, if it points to a task page
+fn page_slug(href: &str, base_url: &str) -> Option<String> {
+    let href = href.strip_prefix(base_url).unwrap_or(href);
+    TASK_PAGE_LINK_RE.captures(href).map(|caps| caps["Slug"].to_string())
+}
+
+// URLs of every task page linked from the index, in the order they are linked
+pub fn discover_task_pages(index_html: &str, base_url: &str) -> Vec<String> {
+    let document = Html::parse_document(index_html);
+    let Ok(selector) = Selector::parse("a[href]") else {
+        return Vec::new();
+    };
+
+    let mut pages: Vec<String> = Vec::new();
+    for link in document.select(&selector) {
+        let Some(slug) = link.value().attr("href").and_then(|href| page_slug(href, base_url)) else {
+            continue;
+        };
+        let url = page_url(base_url, &slug);
+        if !pages.contains(&url) {
+            pages.push(url);
+        }
+    }
+
+    pages
+}
+
+// Pages of the task's other major versions linked from its page, e.g. nuget-command-v0 from
+// nuget-command-v2
+pub fn other_versions(html: &str, url: &str, base_url: &str) -> Vec<String> {
+    let Some(slug) = page_slug(url, base_url) else {
+        return Vec::new();
+    };
+    let Some((task, _)) = slug.rsplit_once("-v") else {
+        return Vec::new();
+    };
+    let own_page = page_url(base_url, &slug);
+
+    discover_task_pages(html, base_url).into_iter()
+        .filter(|page| *page != own_page)
+        .filter(|page| {
+            page_slug(page, base_url)
+                .and_then(|other| other.rsplit_once("-v").map(|(other, _)| other == task))
+                .unwrap_or(false)
+        })
+        .collect()
+}
@@ -0,0 +1,135 @@
+// --- Deprecated Task Detection ---
+// Docs pages of deprecated tasks carry a notice such as "This task is deprecated; use
+// AzureFunctionApp@2 instead." We pick that up so the generated class can be marked
+// [Obsolete] and, optionally, the replacement generated alongside it.
+
+use regex::Regex;
+use lazy_static::lazy_static;
+use scraper::{Html, Selector};
+
+lazy_static! {
+    static ref DEPRECATED_RE: Regex = Regex::new(
+        r"(?i)\b(?:this task|the task|version of (?:this|the) task)\s+(?:is|has been)\s+deprecated\b"
+    ).expect("Invalid Deprecated Regex");
+
+    // The replacement named in a deprecation notice, e.g. "use AzureFunctionApp@2 instead"
+    static ref REPLACEMENT_RE: Regex = Regex::new(
+        r"(?i)\buse\s+(?:the\s+)?(?<TaskName>[A-Za-z]\w*)@(?<TaskVersion>\d+)"
+    ).expect("Invalid Replacement Regex");
+}
+
+// How much of the page following a deprecation notice may name the replacement
+const NOTICE_LENGTH: usize = 300;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskReference {
+    pub task_name: String,
+    pub task_version: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Deprecation {
+    pub replacement: Option<TaskReference>,
+    pub replacement_class: Option<String>, // Set once the replacement has been generated in the same run
+}
+
+impl Deprecation {
+    // Message for the generated [Obsolete] attribute
+    pub fn obsolete_message(&self, task_name: &str, task_version: &str) -> String {
+        match (&self.replacement_class, &self.replacement) {
+            (Some(class), _) => format!("{}@{} is deprecated. Use {} instead.", task_name, task_version, class),
+            (None, Some(r)) => format!("{}@{} is deprecated. Use {}@{} instead.", task_name, task_version, r.task_name, r.task_version),
+            (None, None) => format!("{}@{} is deprecated.", task_name, task_version),
+        }
+    }
+}
+
+// Looks for a deprecation notice in the page content or the task summary
+pub fn detect_deprecation(html: &str, task_summary: &str) -> Option<Deprecation> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("div.content").ok()?;
+    let page_text = document.select(&selector)
+        .flat_map(|e| e.text())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let summary_deprecated = task_summary.to_lowercase().starts_with("deprecated");
+    // Only the text right after the notice is considered, so links to other tasks further
+    // down the page aren't mistaken for the replacement
+    let notice = DEPRECATED_RE.find(&page_text)
+        .map(|m| page_text[m.start()..].chars().take(NOTICE_LENGTH).collect::<String>());
+    if notice.is_none() && !summary_deprecated {
+        return None;
+    }
+
+    // The replacement is named in the notice itself, or failing that in the summary
+    let replacement = notice.iter().map(String::as_str)
+        .chain(std::iter::once(task_summary))
+        .find_map(|text| REPLACEMENT_RE.captures(text))
+        .map(|caps| TaskReference {
+            task_name: caps["TaskName"].to_string(),
+            task_version: caps["TaskVersion"].to_string(),
+        });
+
+    Some(Deprecation { replacement, replacement_class: None })
+}
+
+// For sources without page content, only the task summary can carry the notice
+pub fn detect_in_summary(task_summary: &str) -> Option<Deprecation> {
+    if !task_summary.to_lowercase().starts_with("deprecated") {
+        return None;
+    }
+    Some(from_message(task_summary))
+}
+
+// For sources that flag deprecation explicitly, e.g. task.json's 'deprecated' and 'deprecationMessage'
+pub fn from_message(message: &str) -> Deprecation {
+    let replacement = REPLACEMENT_RE.captures(message).map(|caps| TaskReference {
+        task_name: caps["TaskName"].to_string(),
+        task_version: caps["TaskVersion"].to_string(),
+    });
+    Deprecation { replacement, replacement_class: None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(content: &str) -> String {
+        format!("<html><body><div class=\"content\">{}</div></body></html>", content)
+    }
+
+    #[test]
+    fn notices_name_the_replacement() {
+        let deprecation = detect_deprecation(
+            &page("<p>This task is deprecated; use <a href=\"azure-function-app-v2\">AzureFunctionApp@2</a> instead.</p>"),
+            "Update a function app.",
+        ).unwrap();
+        assert_eq!(deprecation.replacement, Some(TaskReference { task_name: "AzureFunctionApp".to_string(), task_version: "2".to_string() }));
+        assert_eq!(deprecation.obsolete_message("AzureFunctionApp", "1"), "AzureFunctionApp@1 is deprecated. Use AzureFunctionApp@2 instead.");
+    }
+
+    #[test]
+    fn only_the_text_after_the_notice_names_the_replacement() {
+        let far_link = format!("<p>This task is deprecated.</p><p>{}</p><p>Use Npm@1 to install packages.</p>", "Lorem ipsum. ".repeat(30));
+        let deprecation = detect_deprecation(&page(&far_link), "Run npm commands.").unwrap();
+        assert_eq!(deprecation.replacement, None);
+        assert_eq!(deprecation.obsolete_message("NpmAuthenticate", "0"), "NpmAuthenticate@0 is deprecated.");
+
+        assert!(detect_deprecation(&page("<p>Use Npm@1 to install packages.</p>"), "Run npm commands.").is_none());
+    }
+
+    #[test]
+    fn summaries_can_carry_the_notice() {
+        let deprecation = detect_in_summary("Deprecated: use AzureCLI@2 instead. Run Azure CLI commands.").unwrap();
+        assert_eq!(deprecation.replacement.map(|r| r.task_name), Some("AzureCLI".to_string()));
+        assert!(detect_in_summary("Run Azure CLI commands.").is_none());
+    }
+
+    #[test]
+    fn a_generated_replacement_is_named_by_its_class() {
+        let mut deprecation = from_message("This task is deprecated. Use the AzureFunctionApp@2 task instead.");
+        deprecation.replacement_class = Some("AzureFunctionAppV2Task".to_string());
+        assert_eq!(deprecation.obsolete_message("AzureFunctionApp", "1"), "AzureFunctionApp@1 is deprecated. Use AzureFunctionAppV2Task instead.");
+    }
+}
@@ -0,0 +1,73 @@
+// --- Parse Diagnostics ---
+// What the parser noticed about a snippet, each pointing at the offending line and columns.
+// They are collected per task while parsing and rendered together at the end of the run, e.g.
+//   error: input documentation not recognized; the input is skipped
+//    --> https://learn.microsoft.com/.../npm-v1:6:19
+//     |
+//   6 |     workingDir: # See the working folder docs
+//     |                   ^^^^^^^^^^^^^^^^^^^^^^^^^^^
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning, // Parsed, but possibly not as intended
+    Error,   // The line could not be parsed and was skipped; fails the task with --strict
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub line: usize,                     // 1-based line number within the snippet
+    pub columns: std::ops::Range<usize>, // Byte range within the line
+    pub text: String,                    // The line as it appears in the snippet
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>, index: usize, text: &str, columns: std::ops::Range<usize>) -> Self {
+        Diagnostic { severity, message: message.into(), line: index + 1, columns, text: text.to_string() }
+    }
+
+    // Pointing at the whole line, less its indentation
+    pub fn for_line(severity: Severity, message: impl Into<String>, index: usize, text: &str) -> Self {
+        let start = text.len() - text.trim_start().len();
+        Self::new(severity, message, index, text, start..text.trim_end().len().max(start))
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    // 1-based first and last column, as shown to users
+    pub fn column_range(&self) -> (usize, usize) {
+        (self.columns.start + 1, self.columns.end.max(self.columns.start + 1))
+    }
+}
+
+// The diagnostics of one source, rustc style
+pub fn render(location: &str, diagnostics: &[Diagnostic]) -> String {
+    diagnostics.iter()
+        .map(|d| {
+            let severity = match d.severity {
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            };
+            let gutter = " ".repeat(d.line.to_string().len());
+            let (first_column, last_column) = d.column_range();
+            let marker = format!("{}{}", " ".repeat(first_column - 1), "^".repeat(last_column - first_column + 1));
+            format!(
+                "{severity}: {message}\n{gutter}--> {location}:{line}:{column}\n{gutter} |\n{line} | {text}\n{gutter} | {marker}\n",
+                severity = severity,
+                message = d.message,
+                gutter = gutter,
+                location = location,
+                line = d.line,
+                column = first_column,
+                text = d.text,
+                marker = marker,
+            )
+        })
+        .collect()
+}
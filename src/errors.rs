@@ -0,0 +1,64 @@
+// --- Failure Classes and Exit Codes ---
+// Each major class of failure exits with its own code so wrapper scripts can tell them apart.
+// Anything not classified here exits with 1. Invalid usage exits with 2, like clap's own errors.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    Usage,      // The command line is invalid, or a file given on it can't be read
+    Network,    // A page or resource could not be fetched
+    Extraction, // No (matching) YAML snippet could be extracted from a page, or (--strict) parsed fully
+    NoInputs,   // A snippet was parsed, but none of its inputs could be
+    Emission,   // The generated output could not be written
+    Drift,      // --check found the generated output out of date
+}
+
+impl FailureKind {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            FailureKind::Usage => 2,
+            FailureKind::Network => 3,
+            FailureKind::Extraction => 4,
+            FailureKind::NoInputs => 5,
+            FailureKind::Emission => 6,
+            FailureKind::Drift => 7,
+        }
+    }
+}
+
+// Shown at the end of '--help'
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  Success
+  1  Unexpected error
+  2  Invalid command line usage, or an input file could not be read
+  3  Network error while fetching a page or resource
+  4  No (matching) YAML snippet could be extracted from a page, or with --strict,
+     part of it could not be parsed
+  5  A snippet was parsed, but none of its inputs could be
+  6  The generated output could not be written
+  7  --check found the generated output out of date";
+
+#[derive(Debug)]
+pub struct Failure {
+    pub kind: FailureKind,
+    message: String,
+}
+
+impl Failure {
+    pub fn new(kind: FailureKind, message: impl Into<String>) -> Self {
+        Failure { kind, message: message.into() }
+    }
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+// Exit code for any error bubbling out of the tool
+pub fn exit_code(error: &(dyn std::error::Error + 'static)) -> u8 {
+    error.downcast_ref::<Failure>().map_or(1, |f| f.kind.exit_code())
+}
@@ -0,0 +1,96 @@
+// --- Marketplace Extension Input Source ---
+// Third-party tasks (SonarQube, WhiteSource, ...) have no docs pages in the standard format,
+// but their extensions ship a task.json for every contributed task. An extension is read
+// either as a packaged VSIX, or as an unpacked vss-extension.json next to its task folders.
+
+use std::io::Read;
+use std::path::Path;
+
+use serde_json::Value;
+
+// Contribution type of a pipeline task in an extension manifest
+const TASK_CONTRIBUTION_TYPE: &str = "ms.vss-distributed-task.task";
+
+// Separates the VSIX path from the entry path in an extension task's location
+const VSIX_ENTRY_SEPARATOR: &str = "!/";
+
+// Locations of the task.json of every task an extension contributes. Entries inside a VSIX
+// are located as '<vsix>!/<entry>'.
+pub fn contributed_tasks(path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    if path.to_lowercase().ends_with(".vsix") {
+        return vsix_tasks(path);
+    }
+
+    let manifest = std::fs::read_to_string(path)?;
+    let root = Path::new(path).parent().unwrap_or(Path::new(""));
+    let mut locations = Vec::new();
+    for folder in task_folders(&manifest)? {
+        let folder = root.join(&folder);
+        // A task folder holds its task.json directly, or one versioned sub folder per major version
+        if folder.join("task.json").is_file() {
+            locations.push(folder.join("task.json").display().to_string());
+            continue;
+        }
+        let mut versions: Vec<String> = std::fs::read_dir(&folder)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path().join("task.json"))
+            .filter(|task_json| task_json.is_file())
+            .map(|task_json| task_json.display().to_string())
+            .collect();
+        versions.sort();
+        locations.extend(versions);
+    }
+
+    Ok(locations)
+}
+
+// A packaged VSIX carries its manifest as 'extension.vsomanifest'
+fn vsix_tasks(path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+    let mut manifest = String::new();
+    archive.by_name("extension.vsomanifest")?.read_to_string(&mut manifest)?;
+
+    let folders = task_folders(&manifest)?;
+    let mut entries: Vec<String> = archive.file_names()
+        .filter(|name| {
+            let Some(folder) = name.strip_suffix("/task.json") else {
+                return false;
+            };
+            // The task.json directly in a task folder, or in a versioned sub folder of it
+            folders.iter().any(|f| *f == folder || folder.rsplit_once('/').is_some_and(|(parent, _)| *f == parent))
+        })
+        .map(str::to_string)
+        .collect();
+    entries.sort();
+
+    Ok(entries.into_iter().map(|entry| format!("{}{}{}", path, VSIX_ENTRY_SEPARATOR, entry)).collect())
+}
+
+// Folders of the task contributions declared in an extension manifest
+fn task_folders(manifest: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let manifest: Value = serde_json::from_str(manifest)?;
+    let folders: Vec<String> = manifest["contributions"].as_array()
+        .map(|contributions| contributions.iter()
+            .filter(|c| c["type"].as_str() == Some(TASK_CONTRIBUTION_TYPE))
+            .filter_map(|c| c["properties"]["name"].as_str())
+            .map(|name| name.trim_matches('/').to_string())
+            .collect())
+        .unwrap_or_default();
+
+    if folders.is_empty() {
+        return Err("Extension manifest contributes no pipeline tasks".into());
+    }
+    Ok(folders)
+}
+
+// Reads the task.json at a location returned by contributed_tasks
+pub fn read_task_json(location: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let Some((vsix, entry)) = location.split_once(VSIX_ENTRY_SEPARATOR) else {
+        return Ok(std::fs::read_to_string(location)?);
+    };
+
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(vsix)?)?;
+    let mut json = String::new();
+    archive.by_name(entry)?.read_to_string(&mut json)?;
+    Ok(json)
+}
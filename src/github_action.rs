@@ -0,0 +1,160 @@
+// --- GitHub Actions Input Source ---
+// A sibling of the Azure DevOps sources for teams defining GitHub workflows in C#: reads an
+// action.yml into a task definition with its inputs as typed properties, which is then named
+// and generated like any task, on the --action-base-class. action.yml files are read line by
+// line with the snippet tokenizer, like the docs snippets.
+
+use heck::ToPascalCase;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::snippet_yaml::{self, YamlLine};
+use crate::{build_parameter, normalized_bool, ProcessedParameter};
+
+lazy_static! {
+    // An action.yml on GitHub: https://github.com/<owner>/<repo>/blob/<ref>/[<path>/]action.yml
+    static ref GITHUB_ACTION_URL_RE: Regex = Regex::new(
+        r"^https://(?:github\.com/(?<Owner>[^/]+)/(?<Repo>[^/]+)/blob|raw\.githubusercontent\.com/(?<RawOwner>[^/]+)/(?<RawRepo>[^/]+))/(?<Ref>[^/]+)/(?:(?<Path>.+)/)?action\.ya?ml$"
+    ).expect("Invalid GitHub Action URL Regex");
+}
+
+pub struct ActionDefinition {
+    pub location: String, // Where the action.yml was read from
+    pub name: String,
+    pub description: String,
+    pub uses: String, // What a workflow step 'uses:', e.g. actions/setup-dotnet@v4
+    pub parameters: Vec<ProcessedParameter>,
+}
+
+// What a task definition generated from an action refers to
+#[derive(Debug, Clone)]
+pub struct ActionReference {
+    pub name: String, // For display, e.g. 'Setup .NET Core SDK'
+    pub uses: String,
+}
+
+// What a workflow step 'uses:' for an action.yml: 'owner/repo[/path]@ref' for one on GitHub,
+// or the './path' of a local action in the workflow's repository
+pub fn uses_reference(location: &str) -> String {
+    if let Some(caps) = GITHUB_ACTION_URL_RE.captures(location)
+        && let (Some(owner), Some(repo)) = (caps.name("Owner").or(caps.name("RawOwner")), caps.name("Repo").or(caps.name("RawRepo")))
+    {
+        let path = caps.name("Path").map(|p| format!("/{}", p.as_str())).unwrap_or_default();
+        return format!("{}/{}{}@{}", owner.as_str(), repo.as_str(), path, &caps["Ref"]);
+    }
+
+    let folder = std::path::Path::new(location).parent().map(|p| p.to_string_lossy().replace('\\', "/")).unwrap_or_default();
+    format!("./{}", folder.trim_start_matches("./"))
+}
+
+pub fn parse_action_yml(yaml: &str, location: &str) -> Result<ActionDefinition, Box<dyn std::error::Error>> {
+    let raw_lines: Vec<&str> = yaml.lines().collect();
+    let lines: Vec<YamlLine> = raw_lines.iter().map(|line| snippet_yaml::tokenize(line)).collect();
+    let mut name = None;
+    let mut description = String::new();
+    let mut parameters = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let Some(key) = line.key.as_ref().filter(|key| line.indent == 0 && !key.commented_out) else {
+            continue;
+        };
+        match key.name {
+            "name" => name = Some(unquote(line.value)),
+            "description" => description = scalar_value(&raw_lines, i, line.value, 0),
+            "inputs" => parameters = parse_inputs(&lines, &raw_lines, i + 1),
+            _ => {}
+        }
+    }
+
+    Ok(ActionDefinition {
+        location: location.to_string(),
+        name: name.ok_or("action.yml has no 'name'")?,
+        description: if description.is_empty() { "N/A".to_string() } else { description },
+        uses: uses_reference(location),
+        parameters,
+    })
+}
+
+// The inputs block: one key per input, with its description, required and default below it
+fn parse_inputs(lines: &[YamlLine], raw_lines: &[&str], start: usize) -> Vec<ProcessedParameter> {
+    let mut parameters = Vec::new();
+    let mut input_indent = None;
+    let mut block_under = None; // Indent of a key whose block scalar value is being skipped
+    let mut current: Option<(String, String, bool, Option<String>)> = None; // Name, description, required, default
+
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        if line.is_blank() || line.is_comment_only() {
+            continue;
+        }
+        if line.indent == 0 {
+            break; // The next top-level key
+        }
+        if block_under.is_some_and(|indent| line.indent > indent) {
+            continue;
+        }
+        block_under = line.is_block_scalar().then_some(line.indent);
+        let Some(key) = line.key.as_ref().filter(|key| !key.commented_out) else {
+            continue;
+        };
+
+        let input_indent = *input_indent.get_or_insert(line.indent);
+        if line.indent == input_indent {
+            parameters.extend(current.take().map(to_parameter));
+            current = Some((key.name.to_string(), String::new(), false, None));
+        } else if let Some((_, description, required, default)) = current.as_mut() {
+            let value = scalar_value(raw_lines, i, line.value, line.indent);
+            match key.name {
+                "description" => *description = value,
+                "required" => *required = normalized_bool(&value) == Some("true"),
+                "default" => *default = Some(value),
+                "deprecationMessage" => *description = format!("Deprecated: {} {}", value, description).trim().to_string(),
+                _ => {}
+            }
+        }
+    }
+    parameters.extend(current.map(to_parameter));
+    parameters
+}
+
+// Inputs are untyped strings in GitHub Actions; boolean defaults are the only reliable hint
+fn to_parameter((name, description, required, default): (String, String, bool, Option<String>)) -> ProcessedParameter {
+    let base_csharp_type = match default.as_deref().and_then(normalized_bool) {
+        Some(_) => "bool",
+        None => "string",
+    };
+    let required_status = if required { "Required" } else { "Optional" };
+    let description = if description.is_empty() { name.clone() } else { description };
+    build_parameter(&name, description, base_csharp_type.to_string(), None, required_status, default)
+}
+
+// A scalar value, following '|' and '>' block scalars onto the lines indented below the key
+fn scalar_value(lines: &[&str], key_line: usize, value: &str, key_indent: usize) -> String {
+    if !value.starts_with('|') && !value.starts_with('>') {
+        return unquote(value);
+    }
+
+    let block: Vec<&str> = lines[key_line + 1..].iter()
+        .take_while(|l| l.trim().is_empty() || l.len() - l.trim_start().len() > key_indent)
+        .map(|l| l.trim())
+        .collect();
+    let separator = if value.starts_with('|') { "\n" } else { " " };
+    block.join(separator).trim().to_string()
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+        value[1..value.len() - 1].replace("''", "'")
+    } else if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        value[1..value.len() - 1].replace("\\\"", "\"")
+    } else {
+        value.to_string()
+    }
+}
+
+// Actions are named for display ('Setup .NET Core SDK'), so the class is named after the repo
+pub fn class_name(action: &ActionDefinition) -> String {
+    let (action_name, _) = action.uses.split_once('@').unwrap_or((action.uses.as_str(), ""));
+    let repo = action_name.rsplit('/').next().filter(|r| !r.is_empty() && *r != ".").unwrap_or(&action.name);
+    repo.to_pascal_case() + "Action"
+}
@@ -0,0 +1,508 @@
+// --- HTTP Fetching ---
+// All pages and resources are fetched through here. With --cache-dir, fresh cached copies are
+// served without a request, and stale ones are revalidated with a conditional request.
+// Transient failures (5xx responses, connection errors and timeouts) are retried with
+// jittered exponential backoff. Proxies come from HTTP_PROXY / HTTPS_PROXY / NO_PROXY unless
+// --proxy is given. A personal access token is only ever sent to Azure DevOps hosts, and
+// --header values only to the documentation site and the hosts of the inputs, so redirects are
+// followed here rather than by reqwest, dropping them whenever a redirect leaves those hosts.
+// Batches of pages can be prefetched on --concurrency worker threads, and all requests share
+// one client, so connections are reused, and the --max-requests-per-second token bucket. With --offline, nothing is requested at all.
+// --record saves every fetched page as a fixture, and --replay serves only recorded fixtures.
+// With --wayback-fallback, pages that no longer exist are fetched from the Internet Archive.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use lazy_static::lazy_static;
+
+use reqwest::header;
+use reqwest::StatusCode;
+
+use crate::cache::{self, CachedPage, Validators};
+use crate::errors::{Failure, FailureKind};
+use crate::wayback;
+use crate::{print_diagnostic, Args, ARGS};
+
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:138.0) Gecko/20100101 Firefox/138.0";
+
+// Azure DevOps Services hosts, which always get the PAT; on-prem servers are added with --pat-host
+const AZURE_DEVOPS_HOSTS: &[&str] = &["dev.azure.com", "visualstudio.com"];
+
+// Backoff before the first retry; doubled for each further retry
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+// As many as reqwest follows by default
+const MAX_REDIRECTS: usize = 10;
+
+lazy_static! {
+    // Built on first use; reqwest clients pool their connections and are shared across threads
+    static ref CLIENT: Result<reqwest::blocking::Client, String> = client(&ARGS).map_err(|e| e.to_string());
+
+    // Results of prefetched pages, each taken by the first fetch_html of its URL
+    static ref PREFETCHED: Mutex<HashMap<String, Result<String, Failure>>> = Mutex::new(HashMap::new());
+
+    // Starts full, allowing a burst of up to one second's worth of requests
+    static ref RATE_LIMIT: Mutex<TokenBucket> = Mutex::new(TokenBucket {
+        tokens: ARGS.max_requests_per_second.unwrap_or(1.0).max(1.0),
+        refilled_at: Instant::now(),
+    });
+
+    // Hosts the --header values are sent to: the documentation site's, which --task, --pipeline,
+    // --all and related versions are found on, and those of the inputs, added once resolved
+    static ref HEADER_HOSTS: Mutex<HashSet<String>> = Mutex::new(host(&crate::docs_base_url()).into_iter().collect());
+}
+
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    // Takes a token for a request at the given time, or says how long until one is available
+    fn take(&mut self, rate: f64, now: Instant) -> Result<(), Duration> {
+        let refill = now.duration_since(self.refilled_at).as_secs_f64() * rate;
+        self.tokens = (self.tokens + refill).min(rate.max(1.0));
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(Duration::from_secs_f64((1.0 - self.tokens) / rate))
+    }
+}
+
+struct Response {
+    status: StatusCode,
+    validators: Validators,
+    body: String,
+}
+
+pub fn fetch_html(url: &str) -> Result<String, Failure> {
+    let prefetched = PREFETCHED.lock().unwrap_or_else(|e| e.into_inner()).remove(url);
+    prefetched.unwrap_or_else(|| fetch(url))
+}
+
+// Downloads pages in parallel on up to --concurrency threads, so a batch or crawl isn't bound
+// by one request at a time. The results are handed out by fetch_html.
+pub fn prefetch(urls: impl IntoIterator<Item = String>) {
+    let urls = prefetch_queue(urls);
+    let workers = (ARGS.concurrency as usize).min(urls.len());
+    if workers <= 1 || ARGS.offline || ARGS.replay.is_some() {
+        return;
+    }
+
+    let next = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while let Some(url) = urls.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let result = fetch(url);
+                    PREFETCHED.lock().unwrap_or_else(|e| e.into_inner()).insert(url.clone(), result);
+                }
+            });
+        }
+    });
+}
+
+// The URLs to prefetch, each once, in the order they were given, so the first sources start first
+pub fn prefetch_queue(urls: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    urls.into_iter().filter(|url| seen.insert(url.clone())).collect()
+}
+
+fn fetch(url: &str) -> Result<String, Failure> {
+    if let Some(fixtures) = &ARGS.replay {
+        return replay(fixtures, url);
+    }
+
+    let body = fetch_cached(url)?;
+    if let Some(fixtures) = &ARGS.record
+        && let Err(e) = record(fixtures, url, &body)
+    {
+        eprintln!("Warning: Could not record {} in {}: {}", url, fixtures.display(), e);
+    }
+    Ok(body)
+}
+
+// Fixtures are named like cache entries, with the URL they were fetched from next to them
+pub fn record(fixtures: &Path, url: &str, body: &str) -> std::io::Result<()> {
+    let path = cache::entry_path(fixtures, url);
+    std::fs::create_dir_all(fixtures)?;
+    std::fs::write(&path, body)?;
+    std::fs::write(path.with_extension("url"), format!("{}\n", url))
+}
+
+pub fn replay(fixtures: &Path, url: &str) -> Result<String, Failure> {
+    let path = cache::entry_path(fixtures, url);
+    print_diagnostic(&format!("// Replaying {} from {}", url, path.display()));
+    std::fs::read_to_string(&path)
+        .map_err(|e| Failure::new(FailureKind::Network, format!("No recorded fixture for {} at {}: {}", url, path.display(), e)))
+}
+
+fn fetch_cached(url: &str) -> Result<String, Failure> {
+    let cache_path = ARGS.cache_dir.as_ref().map(|dir| cache::entry_path(dir, url));
+    // --refresh treats every cached copy as stale, so it is revalidated (and updated if changed)
+    if let Some(path) = &cache_path
+        && !ARGS.refresh
+        && let Some(body) = cache::read_fresh(path, Duration::from_secs(ARGS.cache_ttl))
+    {
+        print_diagnostic(&format!("// Using cached {}", path.display()));
+        return Ok(body);
+    }
+
+    let cached = cache_path.as_deref().and_then(cache::read);
+
+    if ARGS.offline {
+        return offline_copy(url, cached, cache_path.as_deref());
+    }
+
+    let response = download_with_retries(url, cached.as_ref().map(|c| &c.validators))
+        .map_err(|e| Failure::new(FailureKind::Network, format!("Failed to fetch {}: {}", url, e)))?;
+
+    if response.status == StatusCode::NOT_FOUND && ARGS.wayback_fallback {
+        return fetch_wayback_snapshot(url);
+    }
+
+    if response.status == StatusCode::NOT_MODIFIED {
+        return not_modified_copy(url, cached, cache_path.as_deref());
+    }
+
+    // Error pages are never taken for the page itself, so they fail as network errors rather
+    // than as pages without a snippet
+    if !response.status.is_success() {
+        return Err(Failure::new(FailureKind::Network, format!("{} returned {}", url, response.status)));
+    }
+
+    if let Some(path) = &cache_path
+        && let Err(e) = cache::write(path, &response.body, &response.validators)
+    {
+        eprintln!("Warning: Could not cache {} at {}: {}", url, path.display(), e);
+    }
+    Ok(response.body)
+}
+
+// Offline, any cached copy will do, however stale
+pub fn offline_copy(url: &str, cached: Option<CachedPage>, cache_path: Option<&Path>) -> Result<String, Failure> {
+    match (cached, cache_path) {
+        (Some(CachedPage { body, .. }), Some(path)) => {
+            print_diagnostic(&format!("// Offline, using cached {}", path.display()));
+            Ok(body)
+        }
+        _ => Err(Failure::new(FailureKind::Network, format!("Cannot fetch {} in offline mode; it isn't in the --cache-dir cache", url))),
+    }
+}
+
+// A 304 confirms the cached copy, which is then fresh for another --cache-ttl
+pub fn not_modified_copy(url: &str, cached: Option<CachedPage>, cache_path: Option<&Path>) -> Result<String, Failure> {
+    match (cached, cache_path) {
+        (Some(CachedPage { body, .. }), Some(path)) => {
+            print_diagnostic(&format!("// {} not modified, using cached {}", url, path.display()));
+            if let Err(e) = cache::touch(path) {
+                eprintln!("Warning: Could not refresh cached {}: {}", path.display(), e);
+            }
+            Ok(body)
+        }
+        _ => Err(Failure::new(FailureKind::Network, format!("{} returned {}, but there is no cached copy of it", url, StatusCode::NOT_MODIFIED))),
+    }
+}
+
+// The latest Internet Archive snapshot of a page that no longer exists
+fn fetch_wayback_snapshot(url: &str) -> Result<String, Failure> {
+    let network_failure = |e: Box<dyn std::error::Error>| Failure::new(FailureKind::Network, format!("Failed to fetch an archived copy of {}: {}", url, e));
+
+    let availability = download_with_retries(&wayback::availability_url(url), None).map_err(network_failure)?;
+    let Some((snapshot_url, timestamp)) = wayback::latest_snapshot(&availability.body) else {
+        return Err(Failure::new(FailureKind::Network, format!("{} was not found, and the Internet Archive has no copy of it", url)));
+    };
+
+    eprintln!(
+        "Warning: {} was not found; generating from the Internet Archive's snapshot of {} instead ({}). It may be out of date.",
+        url, timestamp, snapshot_url
+    );
+    let snapshot = download_with_retries(&snapshot_url, None).map_err(network_failure)?;
+    if !snapshot.status.is_success() {
+        return Err(Failure::new(FailureKind::Network, format!("The Internet Archive's snapshot {} returned {}", snapshot_url, snapshot.status)));
+    }
+    Ok(snapshot.body)
+}
+
+fn download_with_retries(url: &str, validators: Option<&Validators>) -> Result<Response, Box<dyn std::error::Error>> {
+    let mut attempt = 0;
+    loop {
+        let result = download(url, validators);
+        let transient = match &result {
+            Ok(response) => response.status.is_server_error(),
+            Err(e) => e.downcast_ref::<reqwest::Error>().is_some_and(|e| e.is_connect() || e.is_timeout() || e.is_request()),
+        };
+        if !should_retry(transient, attempt, ARGS.retries) {
+            // A server error that outlasted the retries is no page to generate from
+            return match result {
+                Ok(response) if transient => Err(format!("{} after {} attempt(s)", response.status, attempt + 1).into()),
+                result => result,
+            };
+        }
+
+        attempt += 1;
+        let delay = retry_delay(attempt);
+        let reason = match &result {
+            Ok(response) => response.status.to_string(),
+            Err(e) => e.to_string(),
+        };
+        eprintln!("Warning: Fetching {} failed ({}); retry {}/{} in {:.1}s", url, reason, attempt, ARGS.retries, delay.as_secs_f32());
+        std::thread::sleep(delay);
+    }
+}
+
+// Only transient failures are retried, and at most --retries times
+pub fn should_retry(transient: bool, attempt: u32, retries: u32) -> bool {
+    transient && attempt < retries
+}
+
+// Exponential backoff with up to 50% random jitter, so parallel runs don't retry in lockstep
+pub fn retry_delay(attempt: u32) -> Duration {
+    let backoff = RETRY_BASE_DELAY * 2u32.saturating_pow(attempt - 1);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.subsec_nanos());
+    backoff + backoff.mul_f64(f64::from(nanos % 1000) / 2000.0)
+}
+
+// Blocks until the rate limit allows another request
+fn throttle() {
+    let Some(rate) = ARGS.max_requests_per_second else {
+        return;
+    };
+
+    loop {
+        // Not locked while waiting, so other threads can take tokens refilled meanwhile
+        let taken = RATE_LIMIT.lock().unwrap_or_else(|e| e.into_inner()).take(rate, Instant::now());
+        match taken {
+            Ok(()) => return,
+            Err(wait) => std::thread::sleep(wait),
+        }
+    }
+}
+
+fn shared_client() -> Result<&'static reqwest::blocking::Client, Box<dyn std::error::Error>> {
+    CLIENT.as_ref().map_err(|e| e.clone().into())
+}
+
+fn client(args: &Args) -> Result<reqwest::blocking::Client, Box<dyn std::error::Error>> {
+    let mut builder = reqwest::blocking::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(Duration::from_secs(args.connect_timeout))
+        .timeout(Duration::from_secs(args.request_timeout))
+        .redirect(reqwest::redirect::Policy::none()); // Followed by download()
+
+    if let Some(url) = &args.proxy {
+        // An explicit proxy replaces the environment's, but hosts in NO_PROXY still bypass it
+        let mut proxy = reqwest::Proxy::all(url)?.no_proxy(reqwest::NoProxy::from_env());
+        if let Some(auth) = &args.proxy_auth {
+            let (user, password) = auth.split_once(':').ok_or("--proxy-auth must be '<user>:<password>'")?;
+            proxy = proxy.basic_auth(user, password);
+        }
+        builder = builder.proxy(proxy);
+    }
+
+    Ok(builder.build()?)
+}
+
+// Headers from --header, e.g. cookies or auth headers required by an internal docs mirror, for
+// requests to its hosts, and English as the preferred language unless given otherwise
+fn request_headers(url: &str) -> Result<header::HeaderMap, Box<dyn std::error::Error>> {
+    let mut headers = header::HeaderMap::new();
+    if is_header_host(url) {
+        for entry in &ARGS.header {
+            let (name, value) = entry.split_once(':')
+                .ok_or_else(|| format!("Invalid header '{}'; expected 'Name: value'", entry))?;
+            let mut value = header::HeaderValue::from_str(value.trim())?;
+            value.set_sensitive(true); // Keeps cookies and tokens out of debug output
+            headers.append(header::HeaderName::from_bytes(name.trim().as_bytes())?, value);
+        }
+    }
+    // Locale-less URLs redirect to the page in the preferred language
+    if !ARGS.keep_locale && !headers.contains_key(header::ACCEPT_LANGUAGE) {
+        headers.insert(header::ACCEPT_LANGUAGE, header::HeaderValue::from_str("en-US")?);
+    }
+    Ok(headers)
+}
+
+pub fn is_header_host(url: &str) -> bool {
+    host(url).is_some_and(|host| HEADER_HOSTS.lock().unwrap_or_else(|e| e.into_inner()).contains(&host))
+}
+
+pub fn allow_header_hosts(urls: impl IntoIterator<Item = String>) {
+    HEADER_HOSTS.lock().unwrap_or_else(|e| e.into_inner()).extend(urls.into_iter().filter_map(|url| host(&url)));
+}
+
+fn host(url: &str) -> Option<String> {
+    reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_lowercase))
+}
+
+fn is_azure_devops_url(url: &str) -> bool {
+    let Some(host) = host(url) else {
+        return false;
+    };
+    AZURE_DEVOPS_HOSTS.iter().copied()
+        .chain(ARGS.pat_host.iter().map(String::as_str))
+        .any(|h| host == h.to_lowercase() || host.ends_with(&format!(".{}", h.to_lowercase())))
+}
+
+// Requests a page, conditionally when validators of a cached copy are given, following
+// redirects. Each request of the chain only gets the PAT and --header values its own host may see.
+fn download(url: &str, validators: Option<&Validators>) -> Result<Response, Box<dyn std::error::Error>> {
+    throttle();
+    let client = shared_client()?;
+    let mut url = url.to_string();
+    for _ in 0..=MAX_REDIRECTS {
+        let mut request = client.get(&url).headers(request_headers(&url)?); // A User-Agent among them replaces the default
+        if let Some(pat) = &ARGS.pat
+            && is_azure_devops_url(&url)
+        {
+            // Azure DevOps takes a PAT as the password of basic auth, with any user name
+            request = request.basic_auth("", Some(pat));
+        }
+        if let Some(etag) = validators.and_then(|v| v.etag.as_ref()) {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = validators.and_then(|v| v.last_modified.as_ref()) {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request.send()?;
+        let header_value = |name: header::HeaderName| response.headers().get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        if response.status().is_redirection()
+            && response.status() != StatusCode::NOT_MODIFIED
+            && let Some(location) = header_value(header::LOCATION)
+        {
+            url = reqwest::Url::parse(&url)?.join(&location)?.to_string();
+            continue;
+        }
+
+        return Ok(Response {
+            status: response.status(),
+            validators: Validators {
+                etag: header_value(header::ETAG),
+                last_modified: header_value(header::LAST_MODIFIED),
+            },
+            body: response.text()?,
+        });
+    }
+    Err(format!("more than {} redirects", MAX_REDIRECTS).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_only_go_to_the_docs_site_and_the_inputs() {
+        // --task, --pipeline and --all pages are on the docs site, whichever inputs were given
+        assert!(is_header_host("https://learn.microsoft.com/en-us/azure/devops/pipelines/tasks/reference/npm-v1"));
+        assert!(!is_header_host("https://tasks.contoso.invalid/Npm/task.json"));
+
+        allow_header_hosts(["https://TASKS.contoso.invalid/Npm/task.json".to_string(), "not a url".to_string()]);
+        assert!(is_header_host("https://tasks.contoso.invalid/Other/task.json"));
+        assert!(!is_header_host("https://web.archive.org/web/2024/https://tasks.contoso.invalid/Npm/task.json"));
+    }
+
+    #[test]
+    fn prefetching_fetches_each_url_once_in_order() {
+        let urls = ["https://example.invalid/b", "https://example.invalid/a", "https://example.invalid/b", "https://example.invalid/c", "https://example.invalid/a"];
+        assert_eq!(prefetch_queue(urls.map(str::to_string)), ["https://example.invalid/b", "https://example.invalid/a", "https://example.invalid/c"]);
+        assert!(prefetch_queue(Vec::new()).is_empty());
+    }
+
+    // Serves each request with the path it asked for, recording the paths requested
+    fn serve() -> (String, std::sync::Arc<Mutex<Vec<String>>>) {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requested = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let log = requested.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let log = log.clone();
+                std::thread::spawn(move || {
+                    let mut reader = BufReader::new(&stream);
+                    let mut request_line = String::new();
+                    reader.read_line(&mut request_line).unwrap();
+                    reader.lines().map_while(Result::ok).take_while(|line| !line.is_empty()).for_each(drop); // The headers
+                    let path = request_line.split(' ').nth(1).unwrap_or_default().to_string();
+                    log.lock().unwrap().push(path.clone());
+                    write!(&stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", path.len(), path).unwrap();
+                });
+            }
+        });
+        (base_url, requested)
+    }
+
+    #[test]
+    fn prefetching_downloads_each_page_once() {
+        let (base_url, requested) = serve();
+        let urls = ["/npm-v1", "/dotnet-core-cli-v2", "/npm-v1"].map(|path| format!("{}{}", base_url, path));
+        prefetch(urls.clone());
+
+        let mut paths = requested.lock().unwrap().clone();
+        paths.sort();
+        assert_eq!(paths, ["/dotnet-core-cli-v2", "/npm-v1"]);
+
+        // The pages are then handed out without being requested again
+        assert_eq!(fetch_html(&urls[0]).unwrap(), "/npm-v1");
+        assert_eq!(fetch_html(&urls[1]).unwrap(), "/dotnet-core-cli-v2");
+        assert_eq!(requested.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn prefetched_results_are_handed_out_once() {
+        let url = "https://example.invalid/prefetched";
+        PREFETCHED.lock().unwrap().insert(url.to_string(), Ok("page".to_string()));
+        assert_eq!(fetch_html(url).unwrap(), "page");
+        assert!(!PREFETCHED.lock().unwrap().contains_key(url));
+    }
+
+    #[test]
+    fn the_rate_limit_allows_a_burst_then_spaces_requests_out() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket { tokens: 2.0, refilled_at: start };
+        assert_eq!(bucket.take(2.0, start), Ok(()));
+        assert_eq!(bucket.take(2.0, start), Ok(()));
+        assert_eq!(bucket.take(2.0, start), Err(Duration::from_millis(500)));
+        assert!(bucket.take(2.0, start + Duration::from_millis(250)).is_err());
+        assert_eq!(bucket.take(2.0, start + Duration::from_millis(500)), Ok(()));
+
+        // An idle bucket refills to one second's worth of requests, and no more
+        let later = start + Duration::from_secs(60);
+        assert_eq!(bucket.take(2.0, later), Ok(()));
+        assert_eq!(bucket.take(2.0, later), Ok(()));
+        assert!(bucket.take(2.0, later).is_err());
+
+        // Below one request per second, a single request is still allowed at once
+        let mut slow = TokenBucket { tokens: 1.0, refilled_at: start };
+        assert_eq!(slow.take(0.5, start), Ok(()));
+        assert_eq!(slow.take(0.5, start), Err(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn requests_give_up_after_the_request_timeout() {
+        use clap::Parser;
+
+        // Accepts the connection but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/npm-v1", listener.local_addr().unwrap());
+
+        let args = Args::parse_from(["sharpliner_task_codegen", "--url", &url, "--request-timeout", "1"]);
+        let started = Instant::now();
+        let error = client(&args).unwrap().get(&url).send().unwrap_err();
+        assert!(error.is_timeout(), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(10));
+        drop(listener);
+    }
+}
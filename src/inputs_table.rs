@@ -0,0 +1,228 @@
+// --- Inputs Section Parsing ---
+// Besides the YAML snippet, a task's docs page documents every input in its Inputs section:
+//   <p><strong><code>command</code></strong> - <strong>Command</strong><br/>
+//   <code>string</code>. Required. Allowed values: <code>ci</code>, ... Default value: <code>install</code>.</p>
+//   <p>Specifies the command and arguments to run.</p>
+// Each part is marked up separately there, so unlike the snippet comments nothing has to be
+// guessed from punctuation.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::{build_parameter, csharp_identifier, split_allowed_values, split_required_status, union_type, InputKind, ProcessedParameter};
+
+lazy_static! {
+    static ref CODE_RE: Regex = Regex::new(
+        r"(?s)<code>(?<Code>.*?)</code>"
+    ).expect("Invalid Code Regex");
+
+    static ref TAG_RE: Regex = Regex::new(
+        r"<[^>]*>"
+    ).expect("Invalid Tag Regex");
+
+    static ref LINE_BREAK_RE: Regex = Regex::new(
+        r"<br\s*/?>"
+    ).expect("Invalid Line Break Regex");
+
+    // The line under an input's name, e.g. "string. Required when command = custom. Allowed values: ..."
+    static ref DETAIL_RE: Regex = Regex::new(
+        r"^(?:Input alias:\s*(?<Alias>[^.]*)\.\s*)?(?<Type>[\w:-]+(?:\s+or\s+[\w:-]+)*)\.\s*(?<Rest>.*)$"
+    ).expect("Invalid Input Detail Regex");
+}
+
+#[derive(Debug, Clone)]
+pub struct DocumentedInput {
+    pub yaml_name: String,
+    pub alias: Option<String>,
+    pub label: String,
+    pub input_type: String, // string, boolean, int, filePath, ...
+    pub required_status: String, // Required, Optional, Required when...
+    pub allowed_values: Vec<String>,
+    pub default_value: Option<String>,
+    pub description: String,
+}
+
+// Every input documented in the page's Inputs section, in documented order
+pub fn parse_inputs_table(html: &str) -> Vec<DocumentedInput> {
+    let document = Html::parse_document(html);
+    let Ok(selector) = Selector::parse("div.content h2, div.content p") else {
+        return Vec::new();
+    };
+
+    let mut inputs: Vec<DocumentedInput> = Vec::new();
+    let mut in_inputs_section = false;
+    for element in document.select(&selector) {
+        if element.value().name() == "h2" {
+            in_inputs_section = element.value().id() == Some("inputs");
+            continue;
+        }
+        if !in_inputs_section {
+            continue;
+        }
+
+        match parse_input_header(&element) {
+            Some(input) => inputs.push(input),
+            // Any other paragraph describes the input above it
+            None => if let Some(input) = inputs.last_mut() {
+                let text = html_text(&element.inner_html());
+                if !text.is_empty() {
+                    input.description = [input.description.as_str(), text.as_str()].join(" ").trim().to_string();
+                }
+            },
+        }
+    }
+
+    inputs
+}
+
+// An input's header paragraph: its name and label, then its type, required status, allowed
+// values and default on the line below
+fn parse_input_header(paragraph: &ElementRef) -> Option<DocumentedInput> {
+    let inner_html = paragraph.inner_html();
+    if !inner_html.trim_start().starts_with("<strong><code>") {
+        return None;
+    }
+
+    let mut lines = LINE_BREAK_RE.splitn(&inner_html, 2);
+    let header = lines.next()?;
+    let detail_html = lines.next().unwrap_or("");
+
+    let yaml_name = html_text(&CODE_RE.captures(header)?["Code"]);
+    let label = html_text(header).split_once(" - ").map(|(_, label)| label.trim().to_string()).unwrap_or_default();
+
+    let detail = html_text(detail_html);
+    let caps = DETAIL_RE.captures(&detail)?;
+    let (required_status, _) = split_required_status(&caps["Rest"])?;
+
+    // Only the code spans between 'Allowed values:' and 'Default value:' are values; any
+    // display names after them are plain text. Lists without code spans are split on commas.
+    let allowed_values = detail_html.split_once("Allowed values:")
+        .map(|(_, values)| values.split("Default value:").next().unwrap_or(values))
+        .map(|values| if CODE_RE.is_match(values) {
+            CODE_RE.captures_iter(values).map(|c| html_text(&c["Code"])).collect()
+        } else {
+            split_allowed_values(html_text(values).trim_end_matches('.'))
+        })
+        .unwrap_or_default();
+    let default_value = detail_html.split_once("Default value:")
+        .and_then(|(_, value)| CODE_RE.captures(value).map(|c| html_text(&c["Code"])));
+
+    Some(DocumentedInput {
+        yaml_name,
+        alias: caps.name("Alias").map(|a| html_text(a.as_str())),
+        label,
+        input_type: caps["Type"].to_string(),
+        required_status,
+        allowed_values,
+        default_value,
+        description: String::new(),
+    })
+}
+
+// Converts a documented input with the same type rules as task.json
+pub fn to_parameter(input: &DocumentedInput) -> ProcessedParameter {
+    let union = union_type(&input.input_type);
+    let (base_csharp_type, enum_options) = match (&union, input.input_type.as_str()) {
+        (Some((union_base_type, _)), _) => (union_base_type.clone(), None),
+        (None, "boolean") => ("bool".to_string(), None),
+        (None, "int" | "integer") => ("int".to_string(), None),
+        // As with the snippet, a single allowed value is no choice to make an enum of
+        (None, "string") if input.allowed_values.len() > 1 => (csharp_identifier(&input.yaml_name), Some(input.allowed_values.clone())),
+        _ => ("string".to_string(), None),
+    };
+
+    let description = if input.description.is_empty() { input.label.clone() } else { input.description.clone() };
+
+    let mut parameter = build_parameter(&input.yaml_name, description, base_csharp_type, enum_options, &input.required_status, input.default_value.clone());
+    parameter.input_kind = InputKind::from_docs(&input.input_type, &input.yaml_name, input.alias.as_deref());
+    parameter.aliases = input.alias.iter().cloned().collect();
+    parameter.union_types = union.map(|(_, types)| types).unwrap_or_default();
+    parameter
+}
+
+// Merges the inputs parsed from the snippet with those documented in the Inputs section. The
+// snippet decides the order and gives the example values; the Inputs section decides types,
+// defaults, required status and descriptions, with a warning wherever the two disagree. Inputs
+// only one of them knows are kept.
+pub fn merge_parameters(snippet: Vec<ProcessedParameter>, table: &[DocumentedInput], location: &str) -> Vec<ProcessedParameter> {
+    if table.is_empty() {
+        return snippet;
+    }
+
+    let mut table: Vec<ProcessedParameter> = table.iter().map(to_parameter).collect();
+    let mut merged = Vec::new();
+    for from_snippet in snippet {
+        let Some(index) = table.iter().position(|p| p.yaml_name == from_snippet.yaml_name) else {
+            merged.push(from_snippet);
+            continue;
+        };
+        let mut from_table = table.remove(index);
+        // Only the snippet says when an input is used
+        from_table.visible_when = from_table.visible_when.or(from_snippet.visible_when.clone());
+        from_table.group = from_snippet.group.clone();
+        from_table.example = from_snippet.example.clone();
+
+        let options = |p: &ProcessedParameter| p.enum_options.as_ref().map_or("none".to_string(), |options| options.join(", "));
+        let default = |p: &ProcessedParameter| p.getter_default_arg.clone().unwrap_or_else(|| "none".to_string());
+        let disagreements = [
+            ("type", from_snippet.csharp_type.clone(), from_table.csharp_type.clone()),
+            ("options", options(&from_snippet), options(&from_table)),
+            ("default", default(&from_snippet), default(&from_table)),
+        ];
+        for (what, snippet_value, table_value) in disagreements {
+            if snippet_value != table_value {
+                eprintln!(
+                    "Warning: The snippet and Inputs section at {} disagree on the {} of '{}' ({} vs {}); using the Inputs section.",
+                    location, what, from_snippet.yaml_name, snippet_value, table_value
+                );
+            }
+        }
+        merged.push(from_table);
+    }
+
+    // Inputs left out of the snippet go last, in documented order
+    merged.extend(table);
+    merged
+}
+
+// Plain text of an HTML fragment
+fn html_text(html: &str) -> String {
+    let text = TAG_RE.replace_all(html, "");
+    let text = text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn documented(allowed_values: &[&str]) -> DocumentedInput {
+        DocumentedInput {
+            yaml_name: "command".to_string(),
+            alias: None,
+            label: "Command".to_string(),
+            input_type: "string".to_string(),
+            required_status: "Required".to_string(),
+            allowed_values: allowed_values.iter().map(|v| v.to_string()).collect(),
+            default_value: Some("install".to_string()),
+            description: String::new(),
+        }
+    }
+
+    #[test]
+    fn only_a_choice_of_allowed_values_becomes_an_enum() {
+        let choice = to_parameter(&documented(&["ci", "install"]));
+        assert_eq!((choice.base_csharp_type.as_str(), choice.enum_options.map(|o| o.len())), ("Command", Some(2)));
+
+        for allowed_values in [&["install"][..], &[]] {
+            let single = to_parameter(&documented(allowed_values));
+            assert_eq!((single.base_csharp_type.as_str(), single.enum_options), ("string", None));
+        }
+    }
+}
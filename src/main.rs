@@ -1,17 +1,121 @@
 use clap::Parser;
-use reqwest;
 use scraper::{Html, Selector};
-use heck::ToPascalCase;
+use heck::{ToKebabCase, ToPascalCase};
 use regex::Regex;
 use lazy_static::lazy_static;
 
+mod accessors;
+mod archive;
+mod builtin_tasks;
+mod cache;
+mod crawl;
+mod deprecation;
+mod diagnostics;
+mod errors;
+mod extension;
+mod github_action;
+mod http;
+mod inputs_table;
+mod manifest;
+mod output;
+mod page_sections;
+mod parse_report;
+mod pipeline;
+mod snippet_yaml;
+mod step_factories;
+mod task_group;
+mod task_json;
+mod templates;
+mod wayback;
+use accessors::{AccessorKind, AccessorSurface};
+use builtin_tasks::BuiltinTaskIndex;
+use deprecation::Deprecation;
+use diagnostics::{Diagnostic, Severity};
+use errors::{Failure, FailureKind};
+use http::fetch_html;
+use output::{GeneratedEnum, GeneratedTask};
+use templates::{ClassContext, CodeTemplates, EnumContext, EnumOptionContext, OutputContext, PropertyContext};
+
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, after_help = errors::EXIT_CODES_HELP)]
 struct Args {
-    /// URL of the Azure DevOps task documentation page
+    /// URL of the Azure DevOps task documentation page. Repeat to generate several tasks in one run.
     /// ( e.g. https://learn.microsoft.com/en-us/azure/devops/pipelines/tasks/reference/npm-v1?view=azure-pipelines )
+    #[arg(short, long, required_unless_present_any = ["task", "url_list", "task_json", "input_file", "yaml_file", "extension", "task_group", "action", "tasks_repo", "pipeline", "all"])]
+    url: Vec<String>,
+
+    /// Task to generate as '<TaskName>@<MajorVersion>' (e.g. NuGetCommand@2); its documentation
+    /// URL is derived from the name. Repeatable; combines with --url.
     #[arg(short, long)]
-    url: String,
+    task: Vec<String>,
+
+    /// File of newline-separated documentation URLs to generate, or '-' to read them from stdin.
+    /// Blank lines and lines starting with '#' are ignored. Combines with --url.
+    #[arg(long)]
+    url_list: Option<String>,
+
+    /// Path of a pipeline YAML (or '-' for stdin) to generate every task it references
+    /// ('- task: <TaskName>@<MajorVersion>') for. Repeatable; combines with --url.
+    #[arg(long)]
+    pipeline: Vec<String>,
+
+    /// Crawl the Azure DevOps task reference index and generate every task it links to
+    #[arg(long)]
+    all: bool,
+
+    /// Path of a saved documentation page (HTML) to generate from without network access, or '-'
+    /// to read from stdin. Repeatable; combines with --url.
+    #[arg(long)]
+    input_file: Vec<String>,
+
+    /// How --input-file content is interpreted: a whole documentation page, or just its YAML snippet
+    #[arg(long, value_enum, default_value_t = InputFormat::Html)]
+    format: InputFormat,
+
+    /// Path of a YAML snippet already copied out of a documentation page, or '-' to read from
+    /// stdin. Skips fetching and extraction. Repeatable; combines with --url.
+    #[arg(long)]
+    yaml_file: Vec<String>,
+
+    /// Path or URL of a task.json (e.g. from the microsoft/azure-pipelines-tasks repo) to generate
+    /// from instead of a documentation page. Repeatable; combines with --url.
+    #[arg(long)]
+    task_json: Vec<String>,
+
+    /// Path of a local clone of microsoft/azure-pipelines-tasks to generate every task under its
+    /// Tasks/ directory from, without network access. With --task-version, only that version's folders.
+    #[arg(long)]
+    tasks_repo: Option<String>,
+
+    /// Path or URL of a task group exported from Azure DevOps (JSON) to generate a class for, with
+    /// the task group's parameters as properties. Repeatable; combines with --url.
+    #[arg(long)]
+    task_group: Vec<String>,
+
+    /// Path or URL of a GitHub Actions action.yml to generate a model for, with its inputs as
+    /// typed properties. Repeatable; combines with --url.
+    #[arg(long, conflicts_with = "manifest")]
+    action: Vec<String>,
+
+    /// Base class for the models generated from --action
+    #[arg(long, default_value = "GitHubAction")]
+    action_base_class: String,
+
+    /// Path of a Marketplace extension, as a packaged .vsix or an unpacked vss-extension.json, to
+    /// generate every task it contributes from their task.json. Repeatable; combines with --url.
+    #[arg(long)]
+    extension: Vec<String>,
+
+    /// Base URL of the task reference pages, to use a mirror of learn.microsoft.com (default:
+    /// https://learn.microsoft.com/en-us/azure/devops/pipelines/tasks/reference/). Task names,
+    /// --all and learn.microsoft.com URLs all resolve to pages under it.
+    #[arg(long)]
+    docs_base_url: Option<String>,
+
+    /// Fetch documentation pages in the locale of their URL instead of rewriting them to en-us.
+    /// Only English pages are fully understood.
+    #[arg(long)]
+    keep_locale: bool,
 
     /// Include the raw original documentation for each option.
     #[arg(short, long)]
@@ -21,13 +125,293 @@ struct Args {
     #[arg(short, long)]
     diagnostic_output: bool,
 
+    /// Explain on stderr how each snippet line and input documentation was parsed: which regex
+    /// matched, its captures, and how the input's type, nullability and default were decided
+    #[arg(long)]
+    explain: bool,
+
     /// Optional base class name for the generated C# class
     #[arg(short, long, default_value = "AzureDevOpsTask")]
     base_class: String,
 
-    /// Optional name for the generated C# class (derived from TaskName if not provided)
+    /// Add attributes stating when inputs are used or required, e.g. [VisibleWhen("command = install")]
+    /// and [RequiredWhen("command", "push")], for analyzers to consume. The attribute classes are
+    /// not generated.
+    #[arg(long)]
+    condition_attributes: bool,
+
+    /// Keep inputs documented as strings as strings, even when their default is a number. By
+    /// default they become ints; inputs documented as int always do.
+    #[arg(long)]
+    no_int_inference: bool,
+
+    /// Keep this input as a string even when its default is a number, e.g. timeoutInMinutes.
+    /// Repeatable; matched against the input's YAML name.
+    #[arg(long, value_name = "INPUT")]
+    no_int_inference_for: Vec<String>,
+
+    /// Type filePath inputs with a generated wrapper type of this name (e.g. FilePath), which
+    /// converts implicitly to and from string, instead of plain string properties
+    #[arg(long)]
+    file_path_type: Option<String>,
+
+    /// Type service connection inputs with a generated wrapper type of this name (e.g.
+    /// ServiceConnectionRef) instead of plain string properties. Unlike --file-path-type, it has
+    /// to be created explicitly, so other strings can't be passed for a service connection.
+    #[arg(long)]
+    service_connection_type: Option<String>,
+
+    /// Major version of the task to generate (e.g. 2). The documentation URL is pointed at that
+    /// version's page and only a snippet for that version is accepted.
+    #[arg(long)]
+    task_version: Option<u32>,
+
+    /// Where a documentation page's inputs are read from: the YAML snippet's comments, the page's
+    /// Inputs section (which documents types, defaults and allowed values more reliably), or both
+    /// merged, with the snippet's order and the Inputs section's details
+    #[arg(long, value_enum, default_value_t = InputsSource::Merged)]
+    inputs_source: InputsSource,
+
+    /// How to type inputs documented with several types, e.g. 'string or boolean'
+    #[arg(long, value_enum, default_value_t = UnionTypes::String)]
+    union_types: UnionTypes,
+
+    /// CSS selector locating YAML snippets on documentation pages. Repeat to give a fallback
+    /// chain: the first selector that matches anything is used.
+    /// [default: 'div.content code.lang-yaml', then 'div.content pre code']
+    #[arg(long)]
+    snippet_selector: Vec<String>,
+
+    /// When a page has several YAML snippets, use the one declaring this major version
+    /// ('- task: Name@N'), without changing which page is fetched. Defaults to --task-version.
+    #[arg(long, conflicts_with = "task_version")]
+    snippet_version: Option<u32>,
+
+    /// When a page has several YAML snippets, use the first one under a heading containing this
+    /// text (case-insensitive)
+    #[arg(long)]
+    snippet_heading: Option<String>,
+
+    /// When a task's docs mark it as deprecated in favour of another task, also generate the
+    /// replacement and point the deprecated class's [Obsolete] message at it
+    #[arg(long)]
+    follow_replacements: bool,
+
+    /// Also generate every other major version of a task linked from its documentation page,
+    /// one class per version (e.g. NuGetCommand0Task and NuGetCommand2Task)
+    #[arg(long)]
+    all_versions: bool,
+
+    /// Optional name for the generated C# class (derived from TaskName if not provided).
+    /// Only valid when generating a single task.
     #[arg(short, long)]
     class_name: Option<String>,
+
+    /// Pattern for the names of generated classes, with the placeholders {TaskName} (the task's
+    /// name in PascalCase) and {Version} (its major version), e.g. '{TaskName}V{Version}Task'.
+    /// Defaults to '{TaskName}Task'.
+    #[arg(long, value_name = "TEMPLATE")]
+    class_name_template: Option<String>,
+
+    /// C# namespace to declare the generated code in, e.g. 'MyCompany.Pipelines.Tasks'.
+    /// Declared file-scoped ('namespace X;') unless --block-namespace is given.
+    #[arg(long)]
+    namespace: Option<String>,
+
+    /// Wrap the generated code in a 'namespace X { ... }' block instead of a file-scoped
+    /// namespace, for projects targeting C# 9 or earlier
+    #[arg(long, requires = "namespace")]
+    block_namespace: bool,
+
+    /// Tera template to render each generated class with, instead of the one of the template
+    /// set. See the README for the variables it is given.
+    #[arg(long, value_name = "PATH")]
+    template: Option<std::path::PathBuf>,
+
+    /// Set of templates to generate code with: the built-in 'sharpliner' (the default) or
+    /// 'minimal' (without XML documentation), or a custom set in 'templates/<NAME>/' of the
+    /// current directory or of the user's config directory
+    #[arg(long, value_name = "NAME")]
+    template_set: Option<String>,
+
+    /// Generate a Validate() method on each class, which throws if an input the task requires
+    /// (always, or under a condition that holds) isn't set
+    #[arg(long)]
+    validate: bool,
+
+    /// Also generate a static factory method per task on a partial class of this name ('Steps'
+    /// if no name is given), e.g. Steps.Npm(command: Command.Ci)
+    #[arg(long, num_args = 0..=1, default_missing_value = "Steps", value_name = "CLASS")]
+    step_factories: Option<String>,
+
+    /// With --out-dir or --single-file, also generate a static class of this name
+    /// ('GeneratedTasks' if no name is given) with a factory method per generated task
+    #[arg(long, num_args = 0..=1, default_missing_value = "GeneratedTasks", value_name = "CLASS")]
+    catalog: Option<String>,
+
+    /// Don't prepend '#nullable enable' to generated files, for projects that enable nullable
+    /// reference types project-wide or not at all
+    #[arg(long)]
+    no_nullable: bool,
+
+    /// Declare the generated classes 'partial', so hand-written members can be added in a
+    /// companion file that survives regeneration
+    #[arg(long)]
+    partial: bool,
+
+    /// Write one '<ClassName>.cs' file per generated class into this directory
+    #[arg(long, conflicts_with = "single_file")]
+    out_dir: Option<std::path::PathBuf>,
+
+    /// With --out-dir, write each enum into its own '<EnumName>.cs' file rather than above the
+    /// class using it, optionally in this subdirectory of --out-dir (e.g. 'Enums')
+    #[arg(long, requires = "out_dir", num_args = 0..=1, default_missing_value = ".", value_name = "SUBDIR")]
+    split_enums: Option<std::path::PathBuf>,
+
+    /// Write the generated C# to this file instead of stdout. Several classes are merged into
+    /// the one file, with their enums.
+    #[arg(short = 'o', long, visible_alias = "output")]
+    single_file: Option<std::path::PathBuf>,
+
+    /// Write a JSON manifest of the parsed task definitions to this path ('-' for stdout)
+    /// instead of generating C#. Intended as input for a C# source generator.
+    #[arg(long, conflicts_with_all = ["out_dir", "single_file"])]
+    manifest: Option<String>,
+
+    /// Save the exact documentation page each class was generated from into this directory, as
+    /// '<TaskName>@<MajorVersion>-<ContentHash>.html'
+    #[arg(long)]
+    snapshot_dir: Option<std::path::PathBuf>,
+
+    /// Also package all generated files and a run summary into this archive (.zip or .tar.gz)
+    #[arg(long)]
+    archive: Option<std::path::PathBuf>,
+
+    /// Check that the files at --out-dir, --single-file or --manifest are up to date instead of
+    /// writing them. Exits with code 7 if any would change.
+    #[arg(long)]
+    check: bool,
+
+    /// Include the generation date in the header comment. Off by default so that
+    /// regenerating an unchanged task produces byte-identical output.
+    #[arg(long)]
+    timestamp: bool,
+
+    /// Cache fetched pages in this directory and reuse them on later runs while they are fresh
+    #[arg(long)]
+    cache_dir: Option<std::path::PathBuf>,
+
+    /// How long cached pages stay fresh, in seconds
+    #[arg(long, default_value_t = 86400)]
+    cache_ttl: u64,
+
+    /// Revalidate every cached page instead of trusting fresh copies, picking up pages that
+    /// changed within --cache-ttl. The cache is still updated.
+    #[arg(long, conflicts_with = "offline")]
+    refresh: bool,
+
+    /// Never use the network: pages come from the --cache-dir cache regardless of age, and
+    /// anything else that would need a request fails immediately
+    #[arg(long)]
+    offline: bool,
+
+    /// Save every fetched page into this directory as a fixture for --replay
+    #[arg(long, conflicts_with = "replay")]
+    record: Option<std::path::PathBuf>,
+
+    /// Serve fetched pages only from fixtures saved with --record, never from the network
+    #[arg(long)]
+    replay: Option<std::path::PathBuf>,
+
+    /// When a page no longer exists (404), generate from its latest Internet Archive snapshot instead
+    #[arg(long)]
+    wayback_fallback: bool,
+
+    /// Seconds to wait for a connection to be established before giving up on a fetch
+    #[arg(long, default_value_t = 10)]
+    connect_timeout: u64,
+
+    /// Seconds to wait for a whole fetch (connecting, sending and reading the response)
+    #[arg(long, default_value_t = 30)]
+    request_timeout: u64,
+
+    /// Proxy to fetch through (e.g. http://proxy.corp:8080), instead of the one in HTTP_PROXY /
+    /// HTTPS_PROXY. Hosts in NO_PROXY still bypass it.
+    #[arg(long)]
+    proxy: Option<String>,
+
+    /// Basic auth credentials for --proxy, as '<user>:<password>'
+    #[arg(long, requires = "proxy", env = "SHARPLINER_CODEGEN_PROXY_AUTH", hide_env_values = true)]
+    proxy_auth: Option<String>,
+
+    /// Extra request header as 'Name: value', e.g. a cookie or auth header for an internal docs
+    /// mirror. Repeatable; overrides the default User-Agent if given.
+    #[arg(long)]
+    header: Vec<String>,
+
+    /// Personal access token to authenticate to Azure DevOps with (dev.azure.com, *.visualstudio.com
+    /// and any --pat-host), e.g. for private task definitions
+    #[arg(long, env = "AZURE_DEVOPS_EXT_PAT", hide_env_values = true)]
+    pat: Option<String>,
+
+    /// Additional host (e.g. an on-prem Azure DevOps Server) to send --pat to. Repeatable.
+    #[arg(long)]
+    pat_host: Vec<String>,
+
+    /// Limit all requests (including retries and parallel downloads) to this many per second,
+    /// e.g. 2 or 0.5, to stay polite during large crawls
+    #[arg(long)]
+    max_requests_per_second: Option<f64>,
+
+    /// How many documentation pages and other remote inputs to download in parallel
+    #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..))]
+    concurrency: u32,
+
+    /// How many times to retry a fetch that failed with a server error, connection error or timeout
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+
+    /// Path or URL of the base class C# source. When given, generated getters use the accessor
+    /// methods (GetString, GetBool, GetEnum, ...) it actually declares instead of assuming them.
+    #[arg(long)]
+    base_class_source: Option<String>,
+
+    /// Path or URL of a Sharpliner built-in task index to check against instead of the bundled one.
+    /// One '<TaskName>@<MajorVersion> <SharplinerClass>' entry per line.
+    #[arg(long)]
+    sharpliner_index: Option<String>,
+
+    /// Skip the check for tasks that Sharpliner already provides a first-class implementation for
+    #[arg(long)]
+    skip_builtin_check: bool,
+
+    /// Fail a task (exit code 4) when any line of its snippet can't be parsed, instead of
+    /// skipping the line. For CI regeneration, where nothing should be silently dropped.
+    #[arg(long)]
+    strict: bool,
+
+    /// Write the snippet lines skipped while parsing, per task, to this JSON file
+    #[arg(long)]
+    parse_report: Option<std::path::PathBuf>,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum InputFormat {
+    Html,
+    Yaml,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum UnionTypes {
+    String, // A string property, with a remark listing the accepted types
+    First,  // A property of the first listed type
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum InputsSource {
+    Snippet, // The comments of the YAML snippet
+    Table,   // The Inputs section of the documentation page
+    Merged,  // Both, with the Inputs section taking precedence
 }
 
 // --- Data Structures ---
@@ -38,6 +422,84 @@ struct ParsedTaskInfo {
     task_name: String,
     task_version: String,
     parameters: Vec<ProcessedParameter>,
+    diagnostics: Vec<Diagnostic>, // What the parser noticed about the snippet, if it came from one
+}
+
+// Where a task definition is read from
+#[derive(Debug, Clone)]
+enum TaskSource {
+    DocsPage(String), // URL of a task's documentation page
+    HtmlFile(String), // Path of a saved documentation page, or '-' for stdin
+    YamlFile(String), // Path of a YAML snippet copied from a documentation page, or '-' for stdin
+    TaskJson(String), // Path or URL of a task.json
+    ExtensionTask(String), // Location of a task.json contributed by a Marketplace extension
+    TaskGroup(String), // Path or URL of an exported task group
+    Action(String), // Path or URL of a GitHub Actions action.yml
+}
+
+impl TaskSource {
+    fn location(&self) -> &str {
+        match self {
+            TaskSource::DocsPage(location)
+            | TaskSource::HtmlFile(location)
+            | TaskSource::YamlFile(location)
+            | TaskSource::TaskJson(location)
+            | TaskSource::ExtensionTask(location)
+            | TaskSource::TaskGroup(location)
+            | TaskSource::Action(location) => location,
+        }
+    }
+
+    // The URL the source is downloaded from, if it is downloaded at all
+    fn url(&self) -> Option<String> {
+        let location = match self {
+            TaskSource::DocsPage(url) => docs_page_url(url),
+            TaskSource::TaskJson(location) => task_json_location(location),
+            TaskSource::TaskGroup(location) | TaskSource::Action(location) => location.clone(),
+            TaskSource::HtmlFile(_) | TaskSource::YamlFile(_) | TaskSource::ExtensionTask(_) => return None,
+        };
+        (location.starts_with("http://") || location.starts_with("https://")).then_some(location)
+    }
+}
+
+// A parsed task along with everything needed to emit it
+struct TaskDefinition {
+    info: ParsedTaskInfo,
+    class_name: String,
+    documentation_url: String,
+    deprecation: Option<Deprecation>,
+    other_versions: Vec<String>, // Docs pages of the task's other major versions, if known
+    output_variables: Vec<OutputVariable>,
+    requirements: TaskRequirements,
+    instance_name_format: Option<String>, // How the UI names instances of the task, e.g. "dotnet $(command)"
+    task_id: Option<String>, // The task's GUID, which tells apart same-named tasks from different extensions
+    remarks: Vec<String>, // Paragraphs of usage guidance, pre-formatted as XML doc text
+    examples: Vec<String>, // YAML snippets showing the task in use
+    steps: Vec<task_group::GroupStep>, // The steps a task group runs
+    source_hash: String, // Of the page or file the task was read from, for the --archive lockfile
+    action: Option<github_action::ActionReference>, // Set for tasks read from an action.yml
+}
+
+// What an agent needs to run the task
+#[derive(Debug, Clone, Default)]
+struct TaskRequirements {
+    demands: Vec<String>, // Capabilities a self-hosted agent must have, e.g. 'npm'
+    minimum_agent_version: Option<String>,
+}
+
+// A variable the task sets for later steps
+#[derive(Debug, Clone)]
+struct OutputVariable {
+    name: String,
+    description: String,
+}
+
+impl TaskDefinition {
+    // Task names are case-insensitive in Azure DevOps
+    fn is_same_task(&self, other: &TaskDefinition) -> bool {
+        self.info.task_name.eq_ignore_ascii_case(&other.info.task_name)
+            && self.info.task_version == other.info.task_version
+    }
 }
 
 // Final processed info for C# generation (same as before)
@@ -48,421 +510,3168 @@ struct ProcessedParameter {
     description: String,
     csharp_type: String, // Final C# type (e.g., "string", "bool?", "NpmCommand")
     enum_options: Option<Vec<String>>,
+    enum_labels: Vec<String>, // Display labels of the enum options, in the same order, when known
+    visible_when: Option<String>, // Condition under which the input is used, e.g. "command = install"
+    group: Option<String>, // Display name of the section the input is listed under, e.g. "Advanced"
+    required_when: Option<String>, // Condition under which the input is required, e.g. "command = push"
+    is_required: bool, // Always required; see required_when for inputs required under a condition
+    aliases: Vec<String>, // Other names the task accepts the input under
+    help: Option<String>, // Longer help text, already formatted for XML docs
+    union_types: Vec<String>, // The types of an input documented with several, e.g. string and boolean
     is_nullable: bool,
     getter_default_arg: Option<String>, // Formatted default value for Get*(... , default)
     base_csharp_type: String, // Type without '?'
+    input_kind: InputKind, // What a string input refers to, from its documented type
+    example: Option<String>, // The value the snippet shows for the input, e.g. 'install'
+}
+
+// Documented input types that are strings underneath but refer to something specific
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum InputKind {
+    Plain,
+    FilePath,
+    SecureFile, // The name of an entry in the Secure Files library
+    ServiceConnection(String), // The name of a service connection of the given kinds, e.g. AzureRM
+}
+
+impl InputKind {
+    // From a documented type such as 'filePath', 'secureFile' or 'connectedService:AzureRM'
+    fn from_type(input_type: &str) -> Self {
+        if let Some(kinds) = input_type.strip_prefix("connectedService") {
+            return InputKind::ServiceConnection(kinds.trim_start_matches(':').to_string());
+        }
+        match input_type {
+            "filePath" => InputKind::FilePath,
+            "secureFile" => InputKind::SecureFile,
+            _ => InputKind::Plain,
+        }
+    }
+
+    // The docs only type service connections as strings, but their task.json name or alias is
+    // connectedServiceName...
+    fn from_docs(input_type: &str, yaml_name: &str, alias: Option<&str>) -> Self {
+        if [Some(yaml_name), alias].into_iter().flatten().any(|name| name.starts_with("connectedService")) {
+            return InputKind::ServiceConnection(String::new());
+        }
+        InputKind::from_type(input_type)
+    }
+
+    // How the kind is named to templates and in the manifest
+    fn name(&self) -> &'static str {
+        match self {
+            InputKind::Plain => "string",
+            InputKind::FilePath => "filePath",
+            InputKind::SecureFile => "secureFile",
+            InputKind::ServiceConnection(_) => "connection",
+        }
+    }
+
+    // Remark added to the documentation of properties of this kind
+    fn remark(&self) -> Option<String> {
+        match self {
+            InputKind::Plain => None,
+            InputKind::FilePath => Some("A path to a file or folder, usually relative to the repository root.".to_string()),
+            InputKind::SecureFile => Some("The name of a secure file uploaded to the Secure Files library (Pipelines > Library).".to_string()),
+            InputKind::ServiceConnection(kinds) if kinds.is_empty() => Some("The name of a service connection.".to_string()),
+            InputKind::ServiceConnection(kinds) => Some(format!("The name of a service connection of type {}.", kinds.replace(',', ", "))),
+        }
+    }
 }
 
 // --- Regex Definitions ---
 lazy_static! {
     // Rule 3: Task definition line
     static ref TASK_LINE_RE: Regex = Regex::new(
-        r"^- task:\s*(?<TaskName>\w+)@(?<TaskVersion>\d+)$"
+        r"^(?:-\s+)?task:\s*(?<TaskName>\w+)(?:@(?<TaskVersion>\d+))?\s*(?:#.*)?$"
     ).expect("Invalid Task Line Regex");
 
-    // Rule 4: Input parameter line
-    static ref INPUT_LINE_RE: Regex = Regex::new(
-        r"^ {3,}(?:#\s*)?(?<InputName>\w+):\s*.*?#\s*(?<Documentation>.*)$"
-    ).expect("Invalid Input Line Regex");
-
-    // For parsing the captured Documentation string (same as METADATA_RE before)
-    static ref DOC_METADATA_RE: Regex = Regex::new(
-       r"^\s*([^.]+?)\s*\.\s*([^.]+?)\s*\.(?:(?:\s*Default:\s*(.+?)\.?$)|(?:(.*?)(?:\.\s*Default:\s*(.+?))?\s*))\.?$"
-    ).expect("Invalid Doc Metadata Regex");
-    // Group 1: Type/Options ('ci' | 'install'..., string, boolean)
-    // Group 2: Required Status (Required, Optional, Required when...)
-    // Group 3: Default value if it's the last part
-    // Group 4: Description (if default is not the last part)
-    // Group 5: Default value (if preceded by description)
+    // Version suffix of a docs page URL, e.g. the '-v1' in '.../npm-v1?view=azure-pipelines'
+    static ref URL_VERSION_RE: Regex = Regex::new(
+        r"-v(?<Version>\d+)(?<Suffix>[?#]|$)"
+    ).expect("Invalid URL Version Regex");
+
+    // Locale segment of a learn.microsoft.com URL, e.g. the 'de-de' in 'https://learn.microsoft.com/de-de/azure/...'
+    static ref URL_LOCALE_RE: Regex = Regex::new(
+        r"(?i)^(?<Host>https?://(?:learn|docs)\.microsoft\.com/)(?:(?<Locale>[a-z]{2}-[a-z]{2,4}(?:-[a-z]{2})?)/)?"
+    ).expect("Invalid URL Locale Regex");
+
+    // The documentation string is parsed clause by clause, e.g.
+    // "'ci' | 'install'. Required. Command. Default: install."
+    // First clause: Type/Options ('ci' | 'install'..., ci | install..., string, boolean, string or boolean)
+    static ref DOC_TYPE_RE: Regex = Regex::new(
+        r"^\s*(?<Type>(?:'[^']*'|[\w:-]+)(?:\s*\|\s*(?:'[^']*'|[\w:-]+)|\s+or\s+[\w:-]+)*)\s*\.\s*(?<Rest>.*)$"
+    ).expect("Invalid Doc Type Regex");
+
+    // Next clause, if present: the input's alias, e.g. "Alias: connectedServiceNameARM."
+    static ref DOC_ALIAS_RE: Regex = Regex::new(
+        r"^(?:Input )?[Aa]lias:\s*(?<Alias>[\w.-]+)\s*\.\s*(?<Rest>.*)$"
+    ).expect("Invalid Doc Alias Regex");
+
+    // Next clause, if present: Required Status (Required, Optional, Required when...)
+    static ref DOC_REQUIRED_RE: Regex = Regex::new(
+        r"^(?<Required>Required when .*?|Required|Optional)\s*\.(?:\s+(?<Rest>.*)|\s*$)"
+    ).expect("Invalid Doc Required Regex");
+
+    // Other phrasings of the required status seen on pages, e.g. "(Required)", "Mandatory." or
+    // "This input is optional.", normalized before DOC_REQUIRED_RE is applied
+    static ref REQUIRED_PHRASING_RE: Regex = Regex::new(
+        r"^(?i:\(\s*)?(?i:(?:this (?:input|parameter|field) is|is)\s+)?(?<Status>(?i:required|mandatory|optional|not required))(?:\s*\)\s*\.?|\s*[.:,;]|\s*$)"
+    ).expect("Invalid Required Phrasing Regex");
+
+    static ref REQUIRED_CONDITION_RE: Regex = Regex::new(
+        r"^(?i:required)\s+(?i:if|only when|when)\s+"
+    ).expect("Invalid Required Condition Regex");
+
+    // Any clause listing the options in prose, e.g. "Allowed values: ci, install, publish."
+    static ref DOC_ALLOWED_VALUES_RE: Regex = Regex::new(
+        r"(?:^|\.\s+)Allowed values:\s*(?<Values>.*?)(?:\.\s+|\.?\s*$)"
+    ).expect("Invalid Doc Allowed Values Regex");
+
+    // Any clause restricting when the input is used, e.g. "Use when command = install."
+    static ref DOC_USE_WHEN_RE: Regex = Regex::new(
+        r"(?:^|\.\s+)Use when (?<Condition>.*?)(?:\.\s+|\.?\s*$)"
+    ).expect("Invalid Doc Use When Regex");
+
+    // Last clause, if present: Default value. Whatever is left in between is the description.
+    static ref DOC_DEFAULT_RE: Regex = Regex::new(
+        r"(?:^|\.\s+|\s)Default:\s*(?<Default>.*?)\.?\s*$"
+    ).expect("Invalid Doc Default Regex");
 }
 
+#[cfg(not(test))]
 lazy_static! {
     static ref ARGS : Args = Args::parse();
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let start_time = std::time::Instant::now(); // Start timing
-    let html_content = fetch_html(&ARGS.url)?;
-
-    print_diagnostic("// Extracting YAML snippet text...");
-    let yaml_text = extract_yaml_snippet(&html_content)?;
+// The test harness has its own command line, so tests run with default options
+#[cfg(test)]
+lazy_static! {
+    static ref ARGS : Args = Args::parse_from([env!("CARGO_PKG_NAME"), "--url", "https://example.invalid"]);
+}
 
-    if yaml_text.is_empty() {
-         eprintln!("Error: Could not find or extract YAML snippet (selector: 'div.content code.lang-yaml').");
-         return Ok(());
+fn main() -> std::process::ExitCode {
+    match run() {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::ExitCode::from(errors::exit_code(e.as_ref()))
+        }
     }
+}
 
-    print_diagnostic("// Parsing YAML snippet line by line...");
-    let parsed_info = parse_yaml_lines(&yaml_text)?;
-
-    if parsed_info.parameters.is_empty() {
-        eprintln!("Warning: No input parameters parsed from the snippet.");
-        // Decide if we should proceed or stop
+// Checks of the command line that clap can't express, made before anything is read or fetched
+fn check_usage(args: &Args) -> Result<(), Failure> {
+    if args.check && args.out_dir.is_none() && args.single_file.is_none() && args.manifest.as_deref().is_none_or(|m| m == "-") {
+        return Err(Failure::new(FailureKind::Usage, "--check requires --out-dir, --single-file or a --manifest path to compare against"));
     }
 
-    print_diagnostic("// Generating C# code...");
-     // Use parsed TaskName for class name if not provided via CLI arg
-     let class_name = ARGS.class_name.clone().unwrap_or_else(|| {
-         parsed_info.task_name.to_pascal_case() + "Task"
-     });
-
-
-    let csharp_code = generate_csharp(
-        &parsed_info.task_summary,
-        &parsed_info.task_name,
-        &parsed_info.task_version,
-        &parsed_info.parameters,
-        &class_name,
-        &ARGS.base_class
-    )?;
-
-    print_diagnostic("\n// --- Generated C# Code ---");
-    println!("{}", csharp_code);
-    print_diagnostic(&format!("// Generation finished in {:?}", start_time.elapsed()));
+    if let Some(path) = &args.archive
+        && archive::archive_format(path).is_none()
+    {
+        return Err(archive::unsupported_format(path));
+    }
 
+    if let Some(namespace) = &args.namespace
+        && !namespace.split('.').all(is_csharp_identifier)
+    {
+        return Err(Failure::new(FailureKind::Usage, format!("--namespace '{}' is not a valid C# namespace", namespace)));
+    }
+    if let Some(catalog) = &args.catalog {
+        if args.out_dir.is_none() && args.single_file.is_none() {
+            return Err(Failure::new(FailureKind::Usage, "--catalog requires --out-dir or --single-file"));
+        }
+        if !is_csharp_identifier(catalog) {
+            return Err(Failure::new(FailureKind::Usage, format!("--catalog '{}' is not a valid C# class name", catalog)));
+        }
+    }
+    for wrapper_type in [&args.file_path_type, &args.service_connection_type].into_iter().flatten() {
+        if !is_csharp_identifier(wrapper_type) {
+            return Err(Failure::new(FailureKind::Usage, format!("'{}' is not a valid C# type name", wrapper_type)));
+        }
+    }
+    if args.file_path_type.is_some() && args.file_path_type == args.service_connection_type {
+        return Err(Failure::new(FailureKind::Usage, "--file-path-type and --service-connection-type must name different types"));
+    }
+    if let Some(factory_class) = args.step_factories.as_deref().filter(|c| !is_csharp_identifier(c)) {
+        return Err(Failure::new(FailureKind::Usage, format!("--step-factories '{}' is not a valid C# class name", factory_class)));
+    }
+    if let Some(template) = &args.class_name_template
+        && !is_csharp_identifier(&template.replace("{TaskName}", "Task").replace("{Version}", "1"))
+    {
+        return Err(Failure::new(FailureKind::Usage, format!("--class-name-template '{}' does not produce a valid C# class name; only {{TaskName}} and {{Version}} can be used", template)));
+    }
+    if args.max_requests_per_second.is_some_and(|rate| !rate.is_finite() || rate <= 0.0) {
+        return Err(Failure::new(FailureKind::Usage, "--max-requests-per-second must be a number greater than 0"));
+    }
+    if let Some(header) = args.header.iter().find(|h| !h.contains(':')) {
+        return Err(Failure::new(FailureKind::Usage, format!("Invalid header '{}'; expected 'Name: value'", header)));
+    }
+    if args.proxy_auth.as_deref().is_some_and(|auth| !auth.contains(':')) {
+        return Err(Failure::new(FailureKind::Usage, "--proxy-auth must be '<user>:<password>'"));
+    }
     Ok(())
 }
 
-fn print_diagnostic(output: &str)
-{
-    if ARGS.diagnostic_output
-    {
-        println!("{}", output);
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let start_time = std::time::Instant::now(); // Start timing
+
+    check_usage(&ARGS)?;
+    let sources = input_sources()?;
+    if ARGS.class_name.is_some() && sources.len() > 1 {
+        return Err(Failure::new(FailureKind::Usage, "--class-name can only be used when generating a single task").into());
     }
-}
 
-// --- HTTP Fetching (same as before) ---
-fn fetch_html(url: &str) -> Result<String, reqwest::Error> {
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:138.0) Gecko/20100101 Firefox/138.0")
-        .build()?;
-    client.get(url).send()?.text()
-}
+    let accessors = match &ARGS.base_class_source {
+        Some(location) => {
+            print_diagnostic("// Reading accessor methods from the base class source...");
+            AccessorSurface::parse_csharp(&read_path_or_url(location)?)
+        }
+        None => AccessorSurface::sharpliner_default(),
+    };
+    let templates = CodeTemplates::load(ARGS.template.as_deref(), ARGS.template_set.as_deref())?;
+
+    // Pages and other remote inputs are downloaded in parallel up front; processing them stays sequential
+    http::prefetch(sources.iter().filter_map(TaskSource::url));
+
+    // Pages that fail extraction or parsing don't stop the other tasks from being generated,
+    // but the first such failure still decides the exit code
+    let mut failures: Vec<Failure> = Vec::new();
+    let mut tasks: Vec<TaskDefinition> = Vec::new();
+    let mut seen_locations = std::collections::HashSet::new();
+    // Queued sources are flagged with whether they were requested, or found as a replacement
+    let mut queue: std::collections::VecDeque<(TaskSource, bool)> = sources.into_iter().map(|s| (s, true)).collect();
+    while let Some((source, requested)) = queue.pop_front() {
+        let url = source.location();
+        if !seen_locations.insert(canonical_url(url)) {
+            eprintln!("Warning: Skipping duplicate source {}", url);
+            continue;
+        }
 
-// --- HTML Snippet Extraction (same as before) ---
-fn extract_yaml_snippet(html: &str) -> Result<String, Box<dyn std::error::Error>> {
-     let document = Html::parse_document(html);
-    // Selector used to locate the code block in the page containing the model structure.
-    // This might need adjustment based on actual page, should things change.
-    let selector = Selector::parse("div.content code.lang-yaml, div.content pre code").map_err(|e| e.to_string())?; // Added fallback selector
-
-    if let Some(code_element) = document.select(&selector).next() {
-        // Prefer collecting text directly, often more reliable than parsing spans unless structure is guaranteed
-        let yaml_content = code_element.text().collect::<String>();
-        Ok(yaml_content)
-    } else {
-        Ok(String::new()) // Return empty if not found
+        match load_task(&source).map(|mut task| {
+            // --class-name only applies to the requested task, not to replacements found along the way
+            if let Some(class_name) = ARGS.class_name.as_ref().filter(|_| requested) {
+                task.class_name = class_name.clone();
+            }
+            task
+        }) {
+            // Different URLs (redirects, aliases, ...) can still document the same task
+            Ok(task) => match tasks.iter().find(|t| t.is_same_task(&task)) {
+                Some(existing) => eprintln!(
+                    "Warning: {} resolves to {}@{}, already generated from {}; generating it once.",
+                    url, task.info.task_name, task.info.task_version, existing.documentation_url
+                ),
+                None => {
+                    if ARGS.follow_replacements
+                        && let Some(replacement) = task.deprecation.as_ref().and_then(|d| d.replacement.as_ref())
+                    {
+                        // The replacement of an extension's task is in that extension, not in the docs
+                        if matches!(source, TaskSource::ExtensionTask(_)) || !pipeline::has_docs_page(&replacement.task_name) {
+                            eprintln!(
+                                "Warning: Not generating {}@{}, the replacement of {}@{}, as it has no documentation page.",
+                                replacement.task_name, replacement.task_version, task.info.task_name, task.info.task_version
+                            );
+                        } else {
+                            print_diagnostic(&format!("// {}@{} is deprecated, also generating {}@{}", task.info.task_name, task.info.task_version, replacement.task_name, replacement.task_version));
+                            queue.push_back((TaskSource::DocsPage(docs_url_for_task(&replacement.task_name, &replacement.task_version)), false));
+                        }
+                    }
+                    if ARGS.all_versions {
+                        for page in &task.other_versions {
+                            print_diagnostic(&format!("// Also generating other version of {} from {}", task.info.task_name, page));
+                            queue.push_back((TaskSource::DocsPage(page.clone()), false));
+                        }
+                    }
+                    tasks.push(task);
+                }
+            },
+            Err(e) if !requested => {
+                // A replacement or other version that can't be generated shouldn't fail the requested tasks
+                eprintln!("Warning: Could not generate related task from {}: {}", url, e);
+            }
+            Err(e) => match e.downcast::<Failure>() {
+                Ok(failure) if matches!(failure.kind, FailureKind::Extraction | FailureKind::NoInputs) => {
+                    eprintln!("Error: {}", failure);
+                    failures.push(*failure);
+                }
+                Ok(failure) => return Err(failure),
+                Err(e) => return Err(e),
+            },
+        }
     }
-}
+    disambiguate_class_names(&mut tasks);
 
+    if !ARGS.skip_builtin_check && !tasks.is_empty() {
+        print_diagnostic("// Checking against Sharpliner built-in tasks...");
+        let index = builtin_task_index()?;
+        for task in tasks.iter().filter(|t| t.action.is_none()) {
+            check_builtin_task(&index, &task.info.task_name, &task.info.task_version);
+        }
+    }
 
-// --- Line-by-Line Parsing Logic ---
-fn parse_yaml_lines(yaml_text: &str) -> Result<ParsedTaskInfo, Box<dyn std::error::Error>> {
-    let lines: Vec<&str> = yaml_text.lines().collect();
-    let mut parameters = Vec::new();
-    let mut task_summary = String::from("N/A");
-    let mut task_name = String::from("UnknownTask");
-    let mut task_version = String::from("0");
+    // Output order must not depend on the order URLs were given in
+    tasks.sort_by(|a, b| a.class_name.cmp(&b.class_name));
+    link_replacement_classes(&mut tasks);
+    disambiguate_enum_names(&mut tasks);
+    for task in tasks.iter_mut() {
+        task.info.parameters = member_parameters(task);
+    }
 
-    let mut line_iter = lines.into_iter().enumerate(); // Use enumerate for index access
+    // In batch runs deprecated tasks are easily missed among the rest
+    let deprecated: Vec<&TaskDefinition> = tasks.iter().filter(|t| t.deprecation.is_some()).collect();
+    if tasks.len() > 1 && !deprecated.is_empty() {
+        eprintln!("Warning: {} of the generated tasks are deprecated:", deprecated.len());
+        for task in &deprecated {
+            eprintln!("  {}", archive::deprecation_line(task));
+        }
+    }
 
-    // Rule 1: Ignore first line (index 0)
-    line_iter.next();
+    // Parse diagnostics are shown together, rather than interleaved with the progress output
+    for task in &tasks {
+        eprint!("{}", diagnostics::render(&task.documentation_url, &task.info.diagnostics));
+    }
+    let skipped = tasks.iter().flat_map(|t| &t.info.diagnostics).filter(|d| d.is_error()).count();
+    if skipped > 0 {
+        eprintln!("Warning: Skipped {} snippet line(s) that could not be parsed; see --parse-report, or use --strict to fail on them.", skipped);
+    }
+    if let Some(path) = &ARGS.parse_report {
+        let report = parse_report::render_parse_report(&tasks)
+            .map_err(|e| Failure::new(FailureKind::Emission, format!("Failed to serialize the parse report: {}", e)))?;
+        std::fs::write(path, report)
+            .map_err(|e| Failure::new(FailureKind::Emission, format!("Failed to write {}: {}", path.display(), e)))?;
+    }
 
-    // Rule 2: Task Summary (index 1)
-    if let Some((_, line)) = line_iter.next() {
-        if let Some(summary) = line.trim().strip_prefix('#') {
-            task_summary = summary.trim().to_string();
+    // Every emitted file, named as it would appear in an archive
+    let mut emitted: Vec<(String, String)> = Vec::new();
+    let mut drifted = Vec::new();
+    if let Some(path) = &ARGS.manifest {
+        print_diagnostic("// Writing task manifest...");
+        let manifest_json = manifest::render_manifest(&tasks, base_class)
+            .map_err(|e| Failure::new(FailureKind::Emission, format!("Failed to serialize the manifest: {}", e)))?;
+        if path == "-" {
+            println!("{}", manifest_json);
+            emitted.push(("manifest.json".to_string(), manifest_json));
         } else {
-             println!("Warning: Line 2 did not seem to contain the task summary comment: '{}'", line);
+            let path = std::path::Path::new(path);
+            output::write_or_check(path, &manifest_json, &mut drifted)?;
+            emitted.push((archive::entry_name(path), manifest_json));
         }
     } else {
-         println!("Warning: Snippet too short, missing task summary line.");
-         // Return default info? Or error?
-         return Ok(ParsedTaskInfo { task_summary, task_name, task_version, parameters });
-    }
+        print_diagnostic("// Generating C# code...");
+        let generated = tasks.iter()
+            .map(|task| generate_csharp(task, base_class(task), &accessors, &templates))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(path) = &ARGS.single_file {
+            // A single class is written just as it would be printed
+            let contents = match generated.as_slice() {
+                [task] if ARGS.catalog.is_none() => task.to_file(&templates)?,
+                _ => output::merge_tasks(&generated, &templates)?,
+            };
+            output::write_or_check(path, &contents, &mut drifted)?;
+            print_diagnostic(&format!("// Wrote {} class(es) to {}", generated.len(), path.display()));
+            emitted.push((archive::entry_name(path), contents));
+        } else if let Some(dir) = &ARGS.out_dir {
+            let files: Vec<(std::path::PathBuf, String)> = match &ARGS.split_enums {
+                Some(enums_dir) => {
+                    let enums_dir = if enums_dir == std::path::Path::new(".") { dir.clone() } else { dir.join(enums_dir) };
+                    generated.iter()
+                        .map(|task| Ok((dir.join(format!("{}.cs", task.class_name)), task.class_file(&templates)?)))
+                        .chain(output::unique_enums(&generated).into_iter()
+                            .map(|(e, used_by)| Ok((enums_dir.join(format!("{}.cs", e.name)), output::enum_file(e, &used_by, &templates)?))))
+                        .collect::<Result<_, Failure>>()?
+                }
+                None => {
+                    // An enum declared in every file using it wouldn't compile, so the enums used by
+                    // several classes are written once, to Enums.cs
+                    let shared: Vec<(&GeneratedEnum, Vec<&str>)> = output::unique_enums(&generated).into_iter()
+                        .filter(|(_, used_by)| used_by.len() > 1)
+                        .collect();
+                    generated.iter()
+                        .map(|task| {
+                            let enums = task.enums.iter().filter(|e| !shared.iter().any(|(s, _)| s.name == e.name)).cloned().collect();
+                            Ok((dir.join(format!("{}.cs", task.class_name)), GeneratedTask { enums, ..task.clone() }.to_file(&templates)?))
+                        })
+                        .chain((!shared.is_empty()).then(|| Ok((dir.join("Enums.cs"), output::shared_enums_file(&shared, &templates)?))))
+                        .collect::<Result<_, Failure>>()?
+                }
+            };
+            let files = match &ARGS.catalog {
+                Some(catalog) => files.into_iter()
+                    .chain(std::iter::once((dir.join(format!("{}.cs", catalog)), output::catalog_file(&generated, catalog, &templates)?)))
+                    .collect(),
+                None => files,
+            };
+
+            // Files that wouldn't change are left alone, so incremental builds don't see them as modified
+            let mut unchanged = 0;
+            for (path, file) in &files {
+                if std::fs::read_to_string(path).ok().as_deref() == Some(file.as_str()) {
+                    unchanged += 1;
+                } else {
+                    output::write_or_check(path, file, &mut drifted)?;
+                    print_diagnostic(&format!("// Wrote {}", path.display()));
+                }
+                emitted.push((archive::entry_name(path.strip_prefix(dir).unwrap_or(path)), file.clone()));
+            }
 
+            // Generated files of tasks that failed this run aren't stale, so nothing is removed then
+            let stale = if failures.is_empty() {
+                let enums_dir = ARGS.split_enums.as_ref().map(|enums_dir| dir.join(enums_dir));
+                let dirs: Vec<&std::path::Path> = [Some(dir), enums_dir.as_ref()].into_iter().flatten().map(|d| d.as_path()).collect();
+                let produced: Vec<std::path::PathBuf> = files.iter().map(|(path, _)| path.clone()).collect();
+                output::stale_files(&dirs, &produced)
+            } else {
+                Vec::new()
+            };
+            for path in &stale {
+                output::remove_or_check(path, &mut drifted)?;
+                print_diagnostic(&format!("// Removed {}", path.display()));
+            }
 
-    // Rule 3: Task Definition (index 2)
-     if let Some((_, line)) = line_iter.next() {
-        if let Some(caps) = TASK_LINE_RE.captures(line.trim()) {
-            task_name = caps["TaskName"].to_string();
-            task_version = caps["TaskVersion"].to_string();
+            if !ARGS.check {
+                let notes: Vec<String> = [(unchanged, "already up to date"), (stale.len(), "no longer generated, removed")].into_iter()
+                    .filter(|(count, _)| *count > 0)
+                    .map(|(count, note)| format!("{} {}", count, note))
+                    .collect();
+                eprintln!(
+                    "Wrote {} file(s) to {}{}",
+                    files.len() - unchanged,
+                    dir.display(),
+                    if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) },
+                );
+            }
         } else {
-             println!("Warning: Line 3 did not match Task definition regex: '{}'", line);
-              // Return? Or continue assuming defaults? Let's continue for now.
+            if generated.len() > 1 {
+                eprintln!("Warning: Writing {} classes to stdout; use --out-dir to write one file per task.", generated.len());
+            }
+            for task in &generated {
+                let file = task.to_file(&templates)?;
+                print_diagnostic("\n// --- Generated C# Code ---");
+                println!("{}", file);
+                emitted.push((format!("{}.cs", task.class_name), file));
+            }
         }
-     } else {
-          println!("Warning: Snippet too short, missing task definition line.");
-          return Ok(ParsedTaskInfo { task_summary, task_name, task_version, parameters });
-     }
+    }
 
-    // Rule 4: Input Parameters (remaining lines)
-    for (index, line) in line_iter {
-        if let Some(caps) = INPUT_LINE_RE.captures(line) {
-            let input_name = caps["InputName"].to_string();
-            let documentation = caps["Documentation"].trim().to_string();
+    // --check only compares, so it doesn't write the archive either
+    if let Some(path) = &ARGS.archive
+        && !ARGS.check
+    {
+        print_diagnostic(&format!("// Writing archive {}...", path.display()));
+        let lockfile = archive::lockfile(&tasks, &emitted)
+            .map_err(|e| Failure::new(FailureKind::Emission, format!("Failed to serialize the lockfile: {}", e)))?;
+        let summary = archive::run_summary(&tasks, &emitted, &failures);
+        emitted.push(("lock.json".to_string(), lockfile));
+        emitted.push(("summary.txt".to_string(), summary));
+        archive::write_archive(path, &emitted)?;
+    }
+    print_diagnostic(&format!("// Generation finished in {:?}", start_time.elapsed()));
 
-            if let Some(processed_param) = parse_input_documentation(&input_name, &documentation) {
-                parameters.push(processed_param);
-            } else {
-                println!("Warning: Failed to parse documentation on line {}: '{}'", index + 1, documentation);
-            }
-        } else if !line.trim().is_empty() && !line.trim().starts_with("inputs:") && !line.trim().starts_with('#') {
-             // Optional: Warn about lines that don't match the expected input format but aren't comments/empty/inputs:
-             // println!("Warning: Skipping non-empty, non-input line {}: '{}'", index + 1, line);
+    if !drifted.is_empty() {
+        for path in &drifted {
+            eprintln!("Out of date: {}", path.display());
         }
+        return Err(Failure::new(FailureKind::Drift, format!("{} generated file(s) are out of date", drifted.len())).into());
     }
 
-    Ok(ParsedTaskInfo { task_summary, task_name, task_version, parameters })
+    match failures.into_iter().next() {
+        Some(failure) => Err(failure.into()),
+        None => Ok(()),
+    }
 }
 
+// All task sources to generate, from --url, --task, --url-list, --pipeline, --all, the file inputs, --tasks-repo, extensions and task groups
+fn input_sources() -> Result<Vec<TaskSource>, Box<dyn std::error::Error>> {
+    let mut urls = ARGS.url.clone();
+
+    let stdin_readers = ARGS.url_list.iter()
+        .chain(&ARGS.input_file)
+        .chain(&ARGS.yaml_file)
+        .chain(&ARGS.pipeline)
+        .filter(|path| *path == "-")
+        .count();
+    if stdin_readers > 1 {
+        return Err(Failure::new(FailureKind::Usage, "Only one input can be read from stdin ('-')").into());
+    }
 
-// --- Documentation String Parsing ---
-fn parse_input_documentation(yaml_name: &str, documentation: &str) -> Option<ProcessedParameter> {
-     DOC_METADATA_RE.captures(documentation).and_then(|caps| {
-        // --- Extract raw parts from regex ---
-        let type_options = caps.get(1).map_or("", |m| m.as_str()).trim().to_string();
-        let required_status = caps.get(2).map_or("", |m| m.as_str()).trim().to_string();
-        let description = caps.get(4).map_or("", |m| m.as_str()).trim().to_string();
-        // Default value can be in group 3 or 5
-        let default_value_str = caps.get(3).or_else(|| caps.get(5)).map(|m| m.as_str().trim().to_string());
-         let final_description = if description.is_empty() && default_value_str.is_some() {
-            // If group 4 was empty because default was last (group 3 matched)
-             // Try to reconstruct description from the original string? Difficult.
-             // For now, leave it empty or use a placeholder.
-             // A better regex might capture description more reliably even if default is last.
-             format!("Details for {}", yaml_name) // Placeholder description
-         } else {
-             description
-         };
-
+    if let Some(list) = &ARGS.url_list {
+        let content = read_input_file(list)?;
+        urls.extend(content.lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(str::to_string));
+    }
 
-        // --- Process extracted parts ---
-        let csharp_name = yaml_name.to_pascal_case();
-        let mut enum_options = None;
-        let mut base_csharp_type = "string".to_string(); // Default assumption
+    for task in &ARGS.task {
+        let (name, version) = task.trim().split_once('@')
+            .filter(|(name, version)| !name.is_empty() && version.parse::<u32>().is_ok())
+            .ok_or_else(|| Failure::new(FailureKind::Usage, format!("Invalid task '{}'; expected '<TaskName>@<MajorVersion>', e.g. NuGetCommand@2", task)))?;
+        if !pipeline::has_docs_page(name) {
+            return Err(Failure::new(FailureKind::Usage, format!("'{}' isn't a built-in task with a documentation page; generate Marketplace extension tasks with --extension", task)).into());
+        }
+        urls.push(docs_url_for_task(name, version));
+    }
 
-        if type_options.contains('|') && type_options.starts_with('\'') {
-            enum_options = Some(type_options.split('|').map(|s| s.trim().replace('\'', "")).collect());
-            base_csharp_type = csharp_name.clone(); // Assume enum type name matches PascalCase property name
-        } else if type_options == "boolean" {
-            base_csharp_type = "bool".to_string();
-        } else if type_options == "string" {
-            // If we see this as a string, and it has a default value, try to parse the default value as an int.
-            // If it parses, set the type to int, otherwise keep it as a string.
-            if default_value_str.is_some()
-            {
-                match default_value_str.as_ref().unwrap().parse::<i32>() {
-                    Ok(_) => {
-                        base_csharp_type = "int".to_string();
-                    },
-                    Err(_) => {
-                        base_csharp_type = "string".to_string();
-                    }
-                }
-            }
-            else {
-                base_csharp_type = "string".to_string();
+    for pipeline in &ARGS.pipeline {
+        let references = pipeline::task_references(&read_input_file(pipeline)?);
+        print_diagnostic(&format!("// Found {} task references in {}", references.len(), pipeline));
+        for reference in references {
+            if pipeline::has_docs_page(&reference.task_name) {
+                urls.push(docs_url_for_task(&reference.task_name, &reference.task_version));
+            } else {
+                eprintln!(
+                    "Warning: Skipping {}@{} from {}; it isn't a built-in task with a documentation page. Generate Marketplace extension tasks with --extension.",
+                    reference.task_name, reference.task_version, pipeline
+                );
             }
-        } // Add other types like 'object', 'secureFile', 'filePath' etc. if needed
+        }
+    }
+
+    if ARGS.all {
+        let index_url = crawl::index_url(&docs_base_url());
+        print_diagnostic(&format!("// Discovering task pages from {}...", index_url));
+        let pages = crawl::discover_task_pages(&fetch_html(&index_url)?, &docs_base_url());
+        print_diagnostic(&format!("// Found {} task pages", pages.len()));
+        urls.extend(pages);
+    }
 
-        let is_required = required_status == "Required";
-        let is_conditionally_required = required_status.starts_with("Required when");
-        let is_optional = required_status == "Optional";
+    let mut task_jsons = ARGS.task_json.clone();
+    if let Some(repo) = &ARGS.tasks_repo {
+        print_diagnostic(&format!("// Scanning {} for tasks...", repo));
+        let tasks = task_json::repo_task_jsons(repo, ARGS.task_version)
+            .map_err(|e| Failure::new(FailureKind::Usage, format!("Could not scan tasks repo {}: {}", repo, e)))?;
+        print_diagnostic(&format!("// Found {} tasks", tasks.len()));
+        task_jsons.extend(tasks);
+    }
 
-        // Apply Nullability Rule (Rule #1)
-        let is_nullable = (is_optional || is_conditionally_required || base_csharp_type == "string") && default_value_str.is_none();
+    let mut extension_tasks = Vec::new();
+    for extension in &ARGS.extension {
+        print_diagnostic(&format!("// Reading extension {}...", extension));
+        let tasks = extension::contributed_tasks(extension)
+            .map_err(|e| Failure::new(FailureKind::Extraction, format!("Could not read extension {}: {}", extension, e)))?;
+        extension_tasks.extend(tasks);
+    }
 
-        let csharp_type = if is_nullable {
-            format!("{}?", base_csharp_type)
-        } else {
-            base_csharp_type.clone()
-        };
+    let sources: Vec<TaskSource> = urls.into_iter().map(TaskSource::DocsPage)
+        .chain(ARGS.input_file.iter().cloned().map(TaskSource::HtmlFile))
+        .chain(ARGS.yaml_file.iter().cloned().map(TaskSource::YamlFile))
+        .chain(task_jsons.into_iter().map(TaskSource::TaskJson))
+        .chain(extension_tasks.into_iter().map(TaskSource::ExtensionTask))
+        .chain(ARGS.task_group.iter().cloned().map(TaskSource::TaskGroup))
+        .chain(ARGS.action.iter().cloned().map(TaskSource::Action))
+        .collect();
+    // --header values are meant for whichever hosts the inputs are on, however they were given
+    http::allow_header_hosts(sources.iter().filter_map(TaskSource::url));
+    Ok(sources)
+}
 
-        // Format Default Arg for Getter (Rule #2)
-        let mut getter_default_arg = None;
-        if !is_nullable && default_value_str.is_some() {
-            getter_default_arg = Some(format_default_value(
-                default_value_str.as_ref().unwrap(),
-                &base_csharp_type,
-                enum_options.is_some() // is_enum
-            ));
+// Several major versions of one task would get the same class name, so each of them gets its
+// version in the name instead, e.g. NuGetCommand0Task and NuGetCommand2Task. The classes of the
+// versions generated together then point at each other, so the latest one is easy to find.
+fn disambiguate_class_names(tasks: &mut [TaskDefinition]) {
+    let class_names: Vec<String> = tasks.iter().map(|t| t.class_name.clone()).collect();
+    for task in tasks.iter_mut() {
+        if class_names.iter().filter(|c| **c == task.class_name).count() > 1 {
+            task.class_name = versioned_class_name(&task.class_name, &task.info.task_version);
         }
+    }
 
-         Some(ProcessedParameter {
-            yaml_name: yaml_name.to_string(),
-            csharp_name,
-            description: final_description,
-            csharp_type,
-            enum_options,
-            is_nullable,
-            getter_default_arg,
-            base_csharp_type,
-        })
-    })
+    let versions: Vec<(String, u32, String)> = tasks.iter()
+        .map(|t| (t.info.task_name.to_lowercase(), t.info.task_version.parse().unwrap_or(0), t.class_name.clone()))
+        .collect();
+    for task in tasks.iter_mut() {
+        let task_name = task.info.task_name.to_lowercase();
+        let version: u32 = task.info.task_version.parse().unwrap_or(0);
+        let others: Vec<&(String, u32, String)> = versions.iter()
+            .filter(|(name, _, class_name)| *name == task_name && *class_name != task.class_name)
+            .collect();
+        let note = match others.iter().filter(|(_, v, _)| *v > version).max_by_key(|(_, v, _)| *v) {
+            Some((_, latest, class_name)) => format!("A newer version of the task, v{}, is generated as <see cref=\"{}\"/>.", latest, class_name),
+            None if !others.is_empty() => format!(
+                "The latest version of the task generated; older versions are generated as {}.",
+                others.iter().map(|(_, _, class_name)| format!("<see cref=\"{}\"/>", class_name)).collect::<Vec<_>>().join(", ")
+            ),
+            None => continue,
+        };
+        task.remarks.insert(0, note);
+    }
 }
 
-// --- Default Value Formatting (mostly same as before) ---
-fn format_default_value(value: &str, base_type: &str, is_enum: bool) -> String {
-    // Handle specific known default values that might not parse correctly otherwise
-    // These often appear in YAML examples
-    if value == "$(BuildConfiguration)" { return "\"$(BuildConfiguration)\"".to_string(); }
-    if value == "$(Build.ArtifactStagingDirectory)/*.nupkg" { return "\"$(Build.ArtifactStagingDirectory)/*.nupkg\"".to_string(); }
-    if value == "**/*.csproj" { return "\"**/*.csproj\"".to_string(); }
-    if value == "$(Build.ArtifactStagingDirectory)" { return "\"$(Build.ArtifactStagingDirectory)\"".to_string(); }
+// The version goes before a 'Task' suffix, e.g. NuGetCommandTask becomes NuGetCommand2Task
+fn versioned_class_name(class_name: &str, task_version: &str) -> String {
+    match class_name.strip_suffix("Task") {
+        Some(name) => format!("{}{}Task", name, task_version),
+        None => format!("{}{}", class_name, task_version),
+    }
+}
 
-   match base_type {
-       "string" => format!("\"{}\"", value.replace('"', "\\\"")),
-       "bool" => value.to_lowercase(), // "true" or "false"
-       _ if is_enum => {
-           let clean_value = value.trim_matches('\'').to_pascal_case();
-           format!("{}.{}", base_type, clean_value)
-       }
-       _ => value.to_string(), // For int, etc.
-   }
+// Enums are shared by name between the classes generated together, so an enum that differs from
+// a same-named enum of an earlier class (by class name) is renamed after its class instead, e.g.
+// the Command of NpmTask becomes NpmCommand. Expects the tasks sorted by class name.
+fn disambiguate_enum_names(tasks: &mut [TaskDefinition]) {
+    let mut definitions: std::collections::HashMap<String, (String, Vec<String>, Vec<String>)> = std::collections::HashMap::new();
+    for task in tasks.iter_mut() {
+        let stem = task.class_name.strip_suffix("Task").filter(|name| !name.is_empty()).unwrap_or(&task.class_name).to_string();
+        for p in task.info.parameters.iter_mut() {
+            let Some(options) = &p.enum_options else { continue };
+            let definition = (p.yaml_name.clone(), options.clone(), p.enum_labels.clone());
+            match definitions.get(&p.base_csharp_type) {
+                None => {}
+                Some(existing) if *existing == definition => continue,
+                Some(_) => {
+                    let renamed = format!("{}{}", stem, p.base_csharp_type);
+                    eprintln!(
+                        "Warning: Enum '{}' of {} differs from another class's; it is generated as '{}'.",
+                        p.base_csharp_type, task.class_name, renamed
+                    );
+                    p.csharp_type = p.csharp_type.replacen(&p.base_csharp_type, &renamed, 1);
+                    p.getter_default_arg = p.getter_default_arg.as_ref()
+                        .map(|default| default.replacen(&format!("{}.", p.base_csharp_type), &format!("{}.", renamed), 1));
+                    p.base_csharp_type = renamed;
+                }
+            }
+            definitions.insert(p.base_csharp_type.clone(), definition);
+        }
+    }
 }
 
+// Points deprecated tasks at the classes generated for their replacements in this run
+fn link_replacement_classes(tasks: &mut [TaskDefinition]) {
+    let generated: Vec<(String, String, String)> = tasks.iter()
+        .map(|t| (t.info.task_name.to_lowercase(), t.info.task_version.clone(), t.class_name.clone()))
+        .collect();
+
+    for task in tasks.iter_mut() {
+        if let Some(deprecation) = task.deprecation.as_mut() {
+            deprecation.replacement_class = deprecation.replacement.as_ref().and_then(|r| {
+                generated.iter()
+                    .find(|(name, version, _)| *name == r.task_name.to_lowercase() && *version == r.task_version)
+                    .map(|(_, _, class)| class.clone())
+            });
+        }
+    }
+}
 
-// --- C# Code Generation ---
-fn generate_csharp(
-    task_summary: &str,
-    task_name: &str,
-    task_version: &str,
-    params: &[ProcessedParameter],
-    class_name: &str,
-    base_class: &str
-) -> Result<String, Box<dyn std::error::Error>> {
-     let mut enums_code = String::new();
-    let mut properties_code = String::new();
+// Builds the task reference URL for a task, e.g. NuGetCommand@2 ->
+// .../tasks/reference/nuget-command-v2. Page slugs are kebab-cased task names, except that
+// a few brand names are kept as one word.
+fn docs_url_for_task(task_name: &str, task_version: &str) -> String {
+    const ONE_WORD_NAMES: &[(&str, &str)] = &[
+        ("NuGet", "Nuget"),
+        ("DotNet", "Dotnet"),
+        ("PowerShell", "Powershell"),
+        ("GitHub", "Github"),
+        ("MSBuild", "Msbuild"),
+        ("VSBuild", "Vsbuild"),
+        ("VSTest", "Vstest"),
+    ];
+
+    let mut name = task_name.to_string();
+    for (compound, word) in ONE_WORD_NAMES {
+        name = name.replace(compound, word);
+    }
+    crawl::page_url(&docs_base_url(), &format!("{}-v{}", name.to_kebab_case(), task_version))
+}
 
-    // --- Generate Enums ---
-    for p in params {
-        if let Some(options) = &p.enum_options {
-            enums_code.push_str(&format!("/// <summary>\n/// Defines options for the {} parameter.\n/// </summary>\n", p.yaml_name));
-            enums_code.push_str(&format!("public enum {} {{\n", p.base_csharp_type));
-            for option in options {
-                 let member_name = option.to_pascal_case();
-                 let alias = option.replace('\'', "");
-                 enums_code.push_str(&format!("    [YamlMember(Alias = \"{}\")]\n", alias));
-                 enums_code.push_str(&format!("    {},\n\n", member_name));
-            }
-            enums_code.push_str("}\n\n");
+// Normalizes a URL for duplicate detection: scheme and host are case-insensitive, and
+// fragments or a trailing slash don't change which page is fetched
+fn canonical_url(url: &str) -> String {
+    let url = url.trim();
+    let url = url.split('#').next().unwrap_or(url);
+    let (path, query) = match url.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (url, None),
+    };
+
+    let path = path.trim_end_matches('/');
+    let path = match path.find("://").map(|i| i + 3) {
+        Some(host_start) => {
+            let host_end = path[host_start..].find('/').map_or(path.len(), |i| host_start + i);
+            format!("{}{}", path[..host_end].to_lowercase(), &path[host_end..])
         }
-     }
+        None => path.to_string(),
+    };
 
+    match query {
+        Some(query) => format!("{}?{}", path, query),
+        None => path,
+    }
+}
 
-    // --- Generate Properties ---
-    for p in params {
-        let mut description_lines = p.description.lines()
-            .map(|l| format!("    /// {}", l.trim()))
-            .collect::<Vec<_>>()
-            .join("\n");
-         // Add the original documentation string as well for reference
-         
-         if ARGS.include_original_documentation
-         {
-            let doc_comment_line = format!("    /// Raw Doc: {}", documentation_escaped(&p.description)); // Need helper to escape XML chars
-            description_lines.push_str(&format!("\n{}", doc_comment_line));
-         }
-
-
-        properties_code.push_str(&format!("    /// <summary>\n{}\n    /// </summary>\n", description_lines));
-        properties_code.push_str("    [YamlIgnore]\n");
-        properties_code.push_str(&format!("    public {} {} {{\n", p.csharp_type, p.csharp_name));
-
-        // Getter logic remains the same based on ProcessedParameter fields
-         properties_code.push_str("        get => ");
-        match p.base_csharp_type.as_str() {
-            "string" => {
-                if let Some(ref default_arg) = p.getter_default_arg {
-                    properties_code.push_str(&format!("GetString(\"{}\", {})!", p.yaml_name, default_arg));
-                } else {
-                    properties_code.push_str(&format!("GetString(\"{}\")", p.yaml_name));
-                }
-            }
-            "bool" => {
-                 if let Some(ref default_arg) = p.getter_default_arg {
-                    properties_code.push_str(&format!("GetBool(\"{}\", {})", p.yaml_name, default_arg));
-                 } else {
-                    properties_code.push_str(&format!("GetBool(\"{}\")", p.yaml_name));
-                 }
-            }
-            "int" => {
-                if let Some(ref default_arg) = p.getter_default_arg {
-                    properties_code.push_str(&format!("GetInt(\"{}\", {})!.Value", p.yaml_name, default_arg));
-                } else {
-                    properties_code.push_str(&format!("GetInt(\"{}\")!.Value", p.yaml_name));
-                }
-            }
-            _ => { // Assume Enum
-                 if let Some(ref default_arg) = p.getter_default_arg {
-                    properties_code.push_str(&format!("GetEnum(\"{}\", {})", p.yaml_name, default_arg));
-                 } else {
-                    properties_code.push_str(&format!("GetNullableEnum<{}>(\"{}\") /* TODO: Verify GetNullableEnum */", p.base_csharp_type, p.yaml_name));
-                 }
-            }
+fn load_task(source: &TaskSource) -> Result<TaskDefinition, Box<dyn std::error::Error>> {
+    match source {
+        TaskSource::DocsPage(url) => load_docs_page(url),
+        TaskSource::HtmlFile(path) if ARGS.format == InputFormat::Yaml => {
+            load_yaml_snippet(read_input_file(path)?, path)
         }
-        properties_code.push_str(";\n");
+        TaskSource::HtmlFile(path) => load_docs_html(&read_input_file(path)?, path),
+        TaskSource::YamlFile(path) => load_yaml_snippet(read_input_file(path)?, path),
+        TaskSource::TaskJson(location) => load_task_json(location),
+        TaskSource::ExtensionTask(location) => load_extension_task(location),
+        TaskSource::TaskGroup(location) => load_task_group(location),
+        TaskSource::Action(location) => load_action(location),
+    }
+}
+
+// The task.json actually read for a location: the raw file of a GitHub page, pointed at
+// --task-version if given
+fn task_json_location(location: &str) -> String {
+    let location = task_json::raw_task_json_url(location);
+    match ARGS.task_version {
+        Some(version) => task_json::versioned_location(&location, version),
+        None => location,
+    }
+}
+
+// Reads and parses a task.json
+fn load_task_json(location: &str) -> Result<TaskDefinition, Box<dyn std::error::Error>> {
+    let location = task_json_location(location);
+    print_diagnostic(&format!("// Reading task.json {}...", location));
+    let json = read_path_or_url(&location)?;
+
+    let parsed = task_json::parse_task_json(&json)
+        .map_err(|e| Failure::new(FailureKind::Extraction, format!("Could not read task definition from {}: {}", location, e)))?;
 
-        // Setter
-        properties_code.push_str(&format!("        init => SetProperty(\"{}\", value);\n", p.yaml_name));
-        properties_code.push_str("    }\n\n");
+    if let Some(version) = ARGS.task_version
+        && parsed.info.task_version != version.to_string()
+    {
+        return Err(Failure::new(FailureKind::Extraction, format!("Task version {} is not documented at {} (found {})", version, location, parsed.info.task_version)).into());
     }
 
-    // --- Assemble Final Class ---
-    let class_summary = format!(
-        "Generated C# model for the Azure DevOps task: {task_name} v{task_version}.\n/// {task_summary}",
-        task_name = task_name,
-        task_version = task_version,
-        task_summary = task_summary // Already trimmed
-    );
-    let escaped_class_summary = class_summary.lines()
-         .map(|l| format!("/// {}", l))
-         .collect::<Vec<_>>()
-         .join("\n");
+    finish_task_json(parsed, &location, &json)
+}
+
+// Reads a task.json contributed by an extension. An extension ships a fixed set of task
+// versions, so --task-version doesn't apply.
+fn load_extension_task(location: &str) -> Result<TaskDefinition, Box<dyn std::error::Error>> {
+    print_diagnostic(&format!("// Reading task.json {}...", location));
+    let json = extension::read_task_json(location)?;
 
-    let final_code = format!(
-r#"// Auto-Generated using '{tool_name}' version {tool_version} on {generation_date}
-// Source Task: {task_name} v{task_version}
-// Source Documentation: {documentation_url}
+    let parsed = task_json::parse_task_json(&json)
+        .map_err(|e| Failure::new(FailureKind::Extraction, format!("Could not read task definition from {}: {}", location, e)))?;
 
-using Sharpliner.AzureDevOps.Tasks;
-using YamlDotNet.Serialization;
+    finish_task_json(parsed, location, &json)
+}
 
-// --- Enums ---
+// Reads an exported task group
+fn load_task_group(location: &str) -> Result<TaskDefinition, Box<dyn std::error::Error>> {
+    print_diagnostic(&format!("// Reading task group {}...", location));
+    let json = read_path_or_url(location)?;
 
-{enums_code}
-/// <summary>
-{escaped_class_summary}
-/// </summary>
-public record class {class_name} : {base_class} {{
-    public {class_name}() : base("{task_name}@{task_version}")
-    {{
-    }}
-{properties_code}
-}}
-"#,
-        tool_name = env!("CARGO_PKG_NAME"),
-        tool_version = env!("CARGO_PKG_VERSION"),
-        generation_date = chrono::Local::now().to_rfc2822(), // Using chrono crate if added
-        task_name = task_name,
-        task_version = task_version,
-        base_class = base_class,
-        enums_code = enums_code.trim(),
-        escaped_class_summary = escaped_class_summary,
-        class_name = class_name,
-        properties_code = properties_code.trim_end(),
-        documentation_url = &ARGS.url
-    );
+    let group = task_group::parse_task_group(&json)
+        .map_err(|e| Failure::new(FailureKind::Extraction, format!("Could not read task group from {}: {}", location, e)))?;
 
-    Ok(final_code)
+    let mut task = finish_task(group.info, location, &json, None)?;
+    task.remarks.push(task_group::remark(&group.display_name));
+    task.steps = group.steps;
+    Ok(task)
 }
 
-// Helper to escape XML characters in documentation comments
-fn documentation_escaped(doc: &str) -> String {
-     doc.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        // Add other replacements if needed
-}
\ No newline at end of file
+// Reads a GitHub Actions action.yml. Actions have no task version or docs page: the task name
+// and version are those of the 'uses:' reference, and local actions have no version at all.
+fn load_action(location: &str) -> Result<TaskDefinition, Box<dyn std::error::Error>> {
+    print_diagnostic(&format!("// Reading action {}...", location));
+    let yaml = read_path_or_url(location)?;
+
+    let action = github_action::parse_action_yml(&yaml, location)
+        .map_err(|e| Failure::new(FailureKind::Extraction, format!("Could not read action from {}: {}", location, e)))?;
+    Ok(action_task(action, &yaml))
+}
+
+fn action_task(action: github_action::ActionDefinition, yaml: &str) -> TaskDefinition {
+    let (task_name, task_version) = action.uses.split_once('@').unwrap_or((action.uses.as_str(), ""));
+
+    TaskDefinition {
+        info: ParsedTaskInfo {
+            task_summary: normalize_text(&action.description),
+            task_name: task_name.to_string(),
+            task_version: task_version.trim_start_matches('v').to_string(),
+            parameters: action.parameters.clone(),
+            diagnostics: Vec::new(),
+        },
+        class_name: github_action::class_name(&action),
+        documentation_url: action.location.clone(),
+        deprecation: None,
+        other_versions: Vec::new(),
+        output_variables: Vec::new(),
+        requirements: TaskRequirements::default(),
+        instance_name_format: None,
+        task_id: None,
+        remarks: Vec::new(),
+        examples: Vec::new(),
+        steps: Vec::new(),
+        source_hash: cache::hash(yaml),
+        action: Some(github_action::ActionReference { name: action.name, uses: action.uses }),
+    }
+}
+
+// Reads a local input file, or stdin when given '-'
+fn read_input_file(path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    if path == "-" {
+        print_diagnostic("// Reading from stdin...");
+        std::io::read_to_string(std::io::stdin())
+            .map_err(|e| Failure::new(FailureKind::Usage, format!("Could not read stdin: {}", e)).into())
+    } else {
+        print_diagnostic(&format!("// Reading {}...", path));
+        Ok(read_local_file(path)?)
+    }
+}
+
+// Base URL of the task reference pages: learn.microsoft.com's, or the --docs-base-url mirror
+fn docs_base_url() -> String {
+    match &ARGS.docs_base_url {
+        Some(base_url) => format!("{}/", base_url.trim_end_matches('/')),
+        None => crawl::DEFAULT_TASK_REFERENCE_URL.to_string(),
+    }
+}
+
+// The documentation page actually fetched for a URL: the en-us page unless --keep-locale, on
+// the --docs-base-url mirror if given, and pointed at --task-version if given
+fn docs_page_url(url: &str) -> String {
+    let mut url = if ARGS.keep_locale { url.to_string() } else { en_us_url(url) };
+    if let Some(slug) = url.strip_prefix(crawl::DEFAULT_TASK_REFERENCE_URL) {
+        url = format!("{}{}", docs_base_url(), slug);
+    }
+    match ARGS.task_version {
+        Some(version) => versioned_url(&url, version),
+        None => url,
+    }
+}
+
+// Runs the fetch -> extract -> parse pipeline for a single documentation page
+fn load_docs_page(url: &str) -> Result<TaskDefinition, Box<dyn std::error::Error>> {
+    let url = &docs_page_url(url);
+
+    print_diagnostic(&format!("// Fetching {}...", url));
+    let html_content = fetch_html(url)?;
+
+    load_docs_html(&html_content, url)
+}
+
+// Extracts and parses the task from the HTML of a documentation page
+fn load_docs_html(html_content: &str, url: &str) -> Result<TaskDefinition, Box<dyn std::error::Error>> {
+    print_diagnostic("// Extracting YAML snippet text...");
+    let snippets = extract_yaml_snippets(html_content)?;
+    let snippet_count = snippets.len();
+    let yaml_text = match select_snippet(snippets, snippet_version(), ARGS.snippet_heading.as_deref()) {
+        Some((index, snippet)) => {
+            print_diagnostic(&format!(
+                "// Using snippet {} of {}{}",
+                index + 1, snippet_count,
+                snippet.heading.map(|h| format!(" (under '{}')", h)).unwrap_or_default()
+            ));
+            snippet.text
+        }
+        None if snippet_count > 0 && (snippet_version().is_some() || ARGS.snippet_heading.is_some()) => {
+            return Err(Failure::new(FailureKind::Extraction, format!("None of the {} snippets at {} matches {}", snippet_count, url, snippet_criteria())).into());
+        }
+        None => String::new(),
+    };
+
+    if yaml_text.is_empty() {
+         return Err(Failure::new(FailureKind::Extraction, format!("Could not find or extract YAML snippet (selectors: '{}') from {}.", snippet_selectors().join("', '"), url)).into());
+    }
+
+    print_diagnostic("// Parsing YAML snippet line by line...");
+    let mut parsed_info = parse_yaml_lines(&yaml_text)?;
+    if parsed_info.task_version.is_empty() || parsed_info.task_name == "UnknownTask" {
+        fill_task_reference_from_page(&mut parsed_info, html_content, url);
+    }
+    // The snippet always names the task; the inputs may also come from the Inputs section
+    if ARGS.inputs_source != InputsSource::Snippet {
+        print_diagnostic("// Parsing the Inputs section...");
+        let table = inputs_table::parse_inputs_table(html_content);
+        parsed_info.parameters = match ARGS.inputs_source {
+            InputsSource::Table => table.iter().map(inputs_table::to_parameter).collect(),
+            _ => inputs_table::merge_parameters(parsed_info.parameters, &table, url),
+        };
+    }
+    let deprecation = deprecation::detect_deprecation(html_content, &parsed_info.task_summary);
+
+    let mut task = finish_task(parsed_info, url, html_content, deprecation)?;
+    task.other_versions = crawl::other_versions(html_content, url, &docs_base_url());
+    task.output_variables = page_sections::parse_output_variables(html_content);
+    task.requirements = page_sections::parse_requirements(html_content);
+    task.examples = page_sections::parse_examples(html_content);
+    task.remarks = page_sections::parse_remarks(html_content).iter().map(|r| task_json::help_markdown_to_xml(r)).collect();
+    if let Some(dir) = &ARGS.snapshot_dir {
+        save_snapshot(dir, &task, html_content)?;
+    }
+    Ok(task)
+}
+
+// When the snippet's '- task: X@N' line is missing or malformed, the URL ('npm-v1') and the
+// page's heading ('Npm@1 - npm v1 task') still say which task it is
+fn fill_task_reference_from_page(parsed_info: &mut ParsedTaskInfo, html_content: &str, url: &str) {
+    let page_reference = page_sections::page_task_reference(html_content);
+    if parsed_info.task_name == "UnknownTask"
+        && let Some((task_name, _)) = &page_reference
+    {
+        print_diagnostic(&format!("// Task name {} taken from the page heading", task_name));
+        parsed_info.task_name = task_name.clone();
+    }
+    if parsed_info.task_version.is_empty() {
+        let url_version = URL_VERSION_RE.captures(url).map(|caps| caps["Version"].to_string());
+        if let Some(version) = url_version.or(page_reference.map(|(_, version)| version)) {
+            print_diagnostic(&format!("// Task version {} taken from the page", version));
+            parsed_info.task_version = version;
+        }
+    }
+}
+
+// Keeps the exact page a task was generated from, named by task and content hash, so surprising
+// output can be traced back to what the page looked like at the time
+fn save_snapshot(dir: &std::path::Path, task: &TaskDefinition, html_content: &str) -> Result<(), Failure> {
+    let path = dir.join(format!("{}@{}-{}.html", task.info.task_name, task.info.task_version, cache::hash(html_content)));
+    print_diagnostic(&format!("// Saving snapshot {}", path.display()));
+    std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&path, html_content))
+        .map_err(|e| Failure::new(FailureKind::Emission, format!("Failed to save snapshot {}: {}", path.display(), e)))
+}
+
+// Parses a YAML snippet that was provided directly rather than extracted from a page
+fn load_yaml_snippet(yaml_text: String, location: &str) -> Result<TaskDefinition, Box<dyn std::error::Error>> {
+    // A bare snippet has no heading, so only its version can be checked
+    let yaml_text = match snippet_version() {
+        Some(version) => select_snippet(vec![Snippet { heading: None, text: yaml_text }], Some(version), None)
+            .map(|(_, snippet)| snippet.text)
+            .ok_or_else(|| Failure::new(FailureKind::Extraction, format!("Task version {} is not documented at {}", version, location)))?,
+        None => yaml_text,
+    };
+
+    print_diagnostic("// Parsing YAML snippet line by line...");
+    let parsed_info = parse_yaml_lines(&yaml_text)?;
+    let deprecation = deprecation::detect_in_summary(&parsed_info.task_summary);
+
+    finish_task(parsed_info, location, &yaml_text, deprecation)
+}
+
+// Checks shared by every source, once a task has been parsed
+fn finish_task(mut parsed_info: ParsedTaskInfo, url: &str, source: &str, deprecation: Option<Deprecation>) -> Result<TaskDefinition, Box<dyn std::error::Error>> {
+    parsed_info.task_summary = normalize_text(&parsed_info.task_summary);
+    if parsed_info.task_version.is_empty() {
+        eprintln!("Warning: Could not determine the version of {} from {}; using 0.", parsed_info.task_name, url);
+        parsed_info.task_version = "0".to_string();
+    }
+    // Some tasks take no inputs at all; only inputs that were there but couldn't be parsed are a failure
+    if parsed_info.parameters.is_empty() {
+        if parsed_info.diagnostics.iter().any(Diagnostic::is_error) {
+            return Err(Failure::new(FailureKind::NoInputs, format!("No input parameters parsed from {}.", url)).into());
+        }
+        print_diagnostic(&format!("// {}@{} takes no inputs", parsed_info.task_name, parsed_info.task_version));
+    }
+    let errors: Vec<Diagnostic> = parsed_info.diagnostics.iter().filter(|d| d.is_error()).cloned().collect();
+    if ARGS.strict && !errors.is_empty() {
+        return Err(Failure::new(FailureKind::Extraction, format!(
+            "{} line(s) of the snippet from {} could not be parsed (--strict):\n{}",
+            errors.len(), url, diagnostics::render(url, &errors).trim_end(),
+        )).into());
+    }
+
+     // Derive the class name from the parsed TaskName; --class-name is applied by the caller
+     let class_name = default_class_name(ARGS.class_name_template.as_deref(), &parsed_info.task_name, &parsed_info.task_version);
+
+    Ok(TaskDefinition {
+        info: parsed_info,
+        class_name,
+        documentation_url: url.to_string(),
+        deprecation,
+        other_versions: Vec::new(),
+        output_variables: Vec::new(),
+        requirements: TaskRequirements::default(),
+        instance_name_format: None,
+        task_id: None,
+        remarks: Vec::new(),
+        examples: Vec::new(),
+        steps: Vec::new(),
+        source_hash: cache::hash(source),
+        action: None,
+    })
+}
+
+// '<PascalTask>Task', or the --class-name-template with its placeholders filled in
+fn default_class_name(template: Option<&str>, task_name: &str, task_version: &str) -> String {
+    match template {
+        Some(template) => template.replace("{TaskName}", &task_name.to_pascal_case()).replace("{Version}", task_version),
+        None => task_name.to_pascal_case() + "Task",
+    }
+}
+
+// finish_task, keeping what task.json says about the task beyond its inputs
+fn finish_task_json(parsed: task_json::TaskJson, location: &str, json: &str) -> Result<TaskDefinition, Box<dyn std::error::Error>> {
+    let mut task = finish_task(parsed.info, location, json, parsed.deprecation)?;
+    task.output_variables = parsed.output_variables;
+    task.requirements = parsed.requirements;
+    task.instance_name_format = parsed.instance_name_format;
+    task.task_id = parsed.task_id;
+    Ok(task)
+}
+
+fn print_diagnostic(output: &str)
+{
+    if ARGS.diagnostic_output
+    {
+        // With '--manifest -' stdout is the manifest, which must stay valid JSON
+        if ARGS.manifest.as_deref() == Some("-")
+        {
+            eprintln!("{}", output);
+        }
+        else
+        {
+            println!("{}", output);
+        }
+    }
+}
+
+// How the parser reached its decisions, for --explain
+fn explain(output: &str) {
+    if ARGS.explain {
+        eprintln!("{}", output);
+    }
+}
+
+// Reads a local file, or fetches the content if given a URL
+fn read_path_or_url(location: &str) -> Result<String, Box<dyn std::error::Error>> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        Ok(fetch_html(location)?)
+    } else {
+        Ok(read_local_file(location)?)
+    }
+}
+
+// A file given on the command line that can't be read is a usage error, like a bad option
+fn read_local_file(path: &str) -> Result<String, Failure> {
+    std::fs::read_to_string(path).map_err(|e| Failure::new(FailureKind::Usage, format!("Could not read {}: {}", path, e)))
+}
+
+// --- Sharpliner Built-in Task Check ---
+// The --sharpliner-index, or the bundled one; read once per run, as it may be downloaded
+fn builtin_task_index() -> Result<BuiltinTaskIndex, Box<dyn std::error::Error>> {
+    Ok(match &ARGS.sharpliner_index {
+        Some(location) => BuiltinTaskIndex::parse(&read_path_or_url(location)?),
+        None => BuiltinTaskIndex::bundled(),
+    })
+}
+
+fn check_builtin_task(index: &BuiltinTaskIndex, task_name: &str, task_version: &str) {
+    if let Some(builtin) = index.find(task_name, task_version) {
+        eprintln!(
+            "Warning: Sharpliner already provides a first-class implementation of {}@{} ({}). Consider using it instead of generating a duplicate wrapper.",
+            task_name, task_version, builtin.class_name
+        );
+    }
+}
+
+// --- HTML Snippet Extraction (same as before) ---
+// A YAML snippet on a docs page, along with the heading it appears under
+struct Snippet {
+    heading: Option<String>,
+    text: String,
+}
+
+// Selectors used to locate the code blocks in the page containing the model structure, in order
+// of preference. These might need adjustment should the docs layout change, which --snippet-selector allows.
+const DEFAULT_SNIPPET_SELECTORS: &[&str] = &["div.content code.lang-yaml", "div.content pre code"];
+
+fn snippet_selectors() -> Vec<String> {
+    if ARGS.snippet_selector.is_empty() {
+        DEFAULT_SNIPPET_SELECTORS.iter().map(|s| s.to_string()).collect()
+    } else {
+        ARGS.snippet_selector.clone()
+    }
+}
+
+// The snippets found by the first selector that finds any
+fn extract_yaml_snippets(html: &str) -> Result<Vec<Snippet>, Box<dyn std::error::Error>> {
+     let document = Html::parse_document(html);
+
+    for snippet_selector in snippet_selectors() {
+        // Headings are matched too, so each snippet can be associated with the last one before it
+        let selector = Selector::parse(&format!("div.content h2, div.content h3, div.content h4, {}", snippet_selector))
+            .map_err(|e| Failure::new(FailureKind::Usage, format!("Invalid snippet selector '{}': {}", snippet_selector, e)))?;
+
+        let mut heading: Option<String> = None;
+        let mut snippets = Vec::new();
+        for element in document.select(&selector) {
+            // Prefer collecting text directly, often more reliable than parsing spans unless structure is guaranteed
+            let text = element.text().collect::<String>();
+            if matches!(element.value().name(), "h2" | "h3" | "h4") {
+                heading = Some(text.trim().to_string());
+            } else {
+                snippets.push(Snippet { heading: heading.clone(), text });
+            }
+        }
+
+        if !snippets.is_empty() {
+            print_diagnostic(&format!("// Selector '{}' matched {} snippet(s)", snippet_selector, snippets.len()));
+            return Ok(snippets);
+        }
+    }
+    Ok(Vec::new())
+}
+
+// The major version snippets must declare: --snippet-version, or else --task-version
+fn snippet_version() -> Option<u32> {
+    ARGS.snippet_version.or(ARGS.task_version)
+}
+
+fn snippet_criteria() -> String {
+    let mut criteria = Vec::new();
+    if let Some(version) = snippet_version() {
+        criteria.push(format!("version {}", version));
+    }
+    if let Some(heading) = &ARGS.snippet_heading {
+        criteria.push(format!("heading '{}'", heading));
+    }
+    criteria.join(" and ")
+}
+
+// Picks the first snippet whose '- task: Name@N' line declares the requested major version, and
+// whose heading contains the requested text (case-insensitive). Without either, the first
+// snippet on the page is the one describing the task's syntax.
+fn select_snippet(snippets: Vec<Snippet>, version: Option<u32>, heading: Option<&str>) -> Option<(usize, Snippet)> {
+    let version = version.map(|v| v.to_string());
+    let heading = heading.map(str::to_lowercase);
+    snippets.into_iter().enumerate().find(|(_, snippet)| {
+        let version_matches = version.as_ref().is_none_or(|version| {
+            snippet.text.lines()
+                .filter_map(|line| TASK_LINE_RE.captures(line.trim()))
+                .any(|caps| caps.name("TaskVersion").is_some_and(|v| v.as_str() == *version))
+        });
+        let heading_matches = heading.as_ref().is_none_or(|heading| {
+            snippet.heading.as_ref().is_some_and(|h| h.to_lowercase().contains(heading.as_str()))
+        });
+        version_matches && heading_matches
+    })
+}
+
+// Points a learn.microsoft.com URL at the en-us page. The metadata parsing only understands the
+// English 'Required' / 'Optional' / 'Default' phrasing of the snippets.
+fn en_us_url(url: &str) -> String {
+    let Some(caps) = URL_LOCALE_RE.captures(url) else {
+        return url.to_string();
+    };
+    if let Some(locale) = caps.name("Locale")
+        && !locale.as_str().eq_ignore_ascii_case("en-us")
+    {
+        eprintln!("Warning: Fetching the en-us page instead of the '{}' one for {}; pass --keep-locale to keep it.", locale.as_str(), url);
+    }
+    URL_LOCALE_RE.replace(url, "${Host}en-us/").into_owned()
+}
+
+// Points a docs page URL at the page for the given major version
+fn versioned_url(url: &str, version: u32) -> String {
+    if !URL_VERSION_RE.is_match(url) {
+        eprintln!("Warning: Could not find a version suffix (e.g. '-v2') in {}; fetching it as-is.", url);
+        return url.to_string();
+    }
+    URL_VERSION_RE.replace(url, format!("-v{}${{Suffix}}", version)).into_owned()
+}
+
+
+// --- Line-by-Line Parsing Logic ---
+
+// Keys of the step itself rather than of its inputs, e.g. 'condition: succeeded()'
+const STEP_KEYS: &[&str] = &["condition", "continueOnError", "displayName", "enabled", "env", "name", "retryCountOnTaskFailure", "target", "timeoutInMinutes"];
+
+fn parse_yaml_lines(yaml_text: &str) -> Result<ParsedTaskInfo, Box<dyn std::error::Error>> {
+    // Snippets copied from some locales have Windows (or bare '\r') line endings
+    let yaml_text = &yaml_text.replace("\r\n", "\n").replace('\r', "\n");
+    let lines: Vec<&str> = yaml_text.lines().collect();
+    let mut parameters = Vec::new();
+    let mut task_summary = String::from("N/A");
+    let mut task_name = String::from("UnknownTask");
+    let mut task_version = String::new(); // Filled in by the caller when the snippet doesn't say
+    let mut diagnostics = Vec::new();
+
+    let mut line_iter = lines.into_iter().enumerate(); // Use enumerate for index access
+
+    // Rule 1: Ignore first line (index 0)
+    line_iter.next();
+
+    // Rule 2: Task Summary (index 1)
+    if let Some((index, line)) = line_iter.next() {
+        if let Some(summary) = line.trim().strip_prefix('#') {
+            task_summary = summary.trim().to_string();
+        } else {
+            diagnostics.push(Diagnostic::for_line(Severity::Warning, "expected the task summary comment", index, line));
+        }
+    } else {
+         diagnostics.push(Diagnostic::new(Severity::Warning, "snippet too short, missing task summary line", 0, yaml_text.lines().next().unwrap_or_default(), 0..0));
+         return Ok(ParsedTaskInfo { task_summary, task_name, task_version, parameters, diagnostics });
+    }
+
+
+    // Rule 3: Task Definition (index 2)
+     if let Some((index, line)) = line_iter.next() {
+        if let Some(caps) = TASK_LINE_RE.captures(line.trim()) {
+            task_name = caps["TaskName"].to_string();
+            match caps.name("TaskVersion") {
+                Some(version) => task_version = version.as_str().to_string(),
+                None => diagnostics.push(Diagnostic::for_line(Severity::Warning, "the task line does not give the task version", index, line)),
+            }
+        } else {
+            // Continue with the default name and version
+            diagnostics.push(Diagnostic::for_line(Severity::Warning, "expected the task line, e.g. '- task: Npm@1'", index, line));
+        }
+     } else {
+          diagnostics.push(Diagnostic::new(Severity::Warning, "snippet too short, missing task definition line", 1, yaml_text.lines().nth(1).unwrap_or_default(), 0..0));
+          return Ok(ParsedTaskInfo { task_summary, task_name, task_version, parameters, diagnostics });
+     }
+
+    // Rule 4: Input Parameters (remaining lines)
+    // The inputs are the keys one level below 'inputs:', each documented by its comment, whatever
+    // the indentation. An input's documentation may wrap onto comment-only lines indented deeper
+    // than the input, so each input is parsed once the line after its documentation is reached.
+    // Any other comment-only line heads a section of inputs, e.g. '# Advanced'.
+    // Keys of the step next to 'inputs:' (condition, displayName, env, ...) are skipped along with
+    // anything nested under them, like the variables under 'env:', as are the lines of an input's
+    // nested or block scalar value.
+    let mut pending: Option<InputLine> = None;
+    let mut group: Option<String> = None;
+    let mut inputs_indent: Option<usize> = None;
+    let mut input_indent: Option<usize> = None;
+    let mut nested_under: Option<usize> = None;
+    let mut seen_inputs = std::collections::HashSet::new();
+    for (index, line) in line_iter {
+        let token = snippet_yaml::tokenize(line);
+        if let Some(input) = pending.as_mut()
+            && nested_under.is_none()
+            && token.indent > input.indent
+            && token.is_comment_only()
+            && let Some(continuation) = &token.comment
+        {
+            explain(&format!("line {}: continues the documentation of '{}'", index + 1, input.name));
+            input.documentation.push(' ');
+            input.documentation.push_str(continuation.text);
+            continue;
+        }
+
+        if let Some(input) = pending.take() {
+            parse_input_line(input, &mut parameters, &mut diagnostics);
+        }
+
+        if token.is_blank() {
+            continue;
+        }
+        if nested_under.is_some_and(|depth| token.indent > depth) {
+            explain(&format!("line {}: nested under the key above; skipped", index + 1));
+            continue;
+        }
+        nested_under = None;
+
+        let Some(key) = &token.key else {
+            match &token.comment {
+                Some(heading) if token.value.is_empty() => {
+                    explain(&format!("line {}: section comment; following inputs are grouped under '{}'", index + 1, heading.text));
+                    group = Some(heading.text.to_string());
+                }
+                _ => {
+                    explain(&format!("line {}: not a key; skipped", index + 1));
+                    diagnostics.push(Diagnostic::for_line(Severity::Error, "not an input line; it is skipped", index, line));
+                }
+            }
+            continue;
+        };
+
+        if inputs_indent.is_none_or(|inputs| token.indent <= inputs) && STEP_KEYS.contains(&key.name) {
+            explain(&format!("line {}: step key '{}'; skipped", index + 1, key.name));
+            nested_under = Some(token.indent);
+            continue;
+        }
+        if key.name == "inputs" && !key.commented_out {
+            inputs_indent = Some(token.indent);
+            input_indent = None;
+            continue;
+        }
+        // Without an 'inputs:' line, any indented key may be an input
+        if !inputs_indent.map_or(token.indent > 0, |inputs| token.indent > inputs) {
+            explain(&format!("line {}: key '{}' outside the inputs; skipped", index + 1, key.name));
+            diagnostics.push(Diagnostic::for_line(Severity::Error, "not an input line; it is skipped", index, line));
+            continue;
+        }
+        let level = *input_indent.get_or_insert(token.indent);
+        if token.indent > level {
+            explain(&format!("line {}: part of the value of the input above; skipped", index + 1));
+            continue;
+        }
+        if token.is_block_scalar() {
+            nested_under = Some(token.indent);
+        }
+
+        let Some(documentation) = &token.comment else {
+            explain(&format!("line {}: input '{}' has no documentation comment; skipped", index + 1, key.name));
+            diagnostics.push(Diagnostic::for_line(Severity::Error, format!("input '{}' has no documentation comment; it is skipped", key.name), index, line));
+            continue;
+        };
+        explain(&format!("line {}: input '{}'{}, documentation = '{}'", index + 1, key.name, if key.commented_out { " (commented out)" } else { "" }, documentation.text));
+        if !seen_inputs.insert(key.name.to_string()) {
+            diagnostics.push(Diagnostic::new(Severity::Warning, format!("input '{}' is listed more than once; keeping the more detailed documentation", key.name), index, line, key.columns.clone()));
+        }
+        pending = Some(InputLine {
+            index,
+            text: line.to_string(),
+            documentation_columns: documentation.start..line.trim_end().len().max(documentation.start),
+            indent: token.indent,
+            name: key.name.to_string(),
+            value: token.value.trim().trim_matches(|c| c == '\'' || c == '"').to_string(),
+            documentation: documentation.text.to_string(),
+            group: group.clone(),
+        });
+    }
+    if let Some(input) = pending {
+        parse_input_line(input, &mut parameters, &mut diagnostics);
+    }
+    let parameters = deduplicate_inputs(parameters);
+
+    Ok(ParsedTaskInfo { task_summary, task_name, task_version, parameters, diagnostics })
+}
+
+
+// Some snippets list an input twice, e.g. once commented out and once with an example value.
+// Each input is kept once, where it first appears, with whichever documentation says the most.
+fn deduplicate_inputs(parameters: Vec<ProcessedParameter>) -> Vec<ProcessedParameter> {
+    let richness = |p: &ProcessedParameter| (
+        p.enum_options.is_some() as usize + p.getter_default_arg.is_some() as usize,
+        p.description.len(),
+    );
+
+    let mut unique: Vec<ProcessedParameter> = Vec::new();
+    for parameter in parameters {
+        match unique.iter_mut().find(|p| p.yaml_name == parameter.yaml_name) {
+            // Reported by the parser, which knows where the repeat is
+            Some(existing) => if richness(&parameter) > richness(existing) {
+                *existing = parameter;
+            },
+            None => unique.push(parameter),
+        }
+    }
+    unique
+}
+
+// An input line of a snippet, with any continuation lines joined onto its documentation
+struct InputLine {
+    index: usize,
+    text: String, // The input's own line
+    documentation_columns: std::ops::Range<usize>, // Where its documentation starts on that line
+    indent: usize,
+    name: String,
+    value: String, // The example value the snippet gives it, if any
+    documentation: String,
+    group: Option<String>, // The section comment above it, if any
+}
+
+fn parse_input_line(input: InputLine, parameters: &mut Vec<ProcessedParameter>, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(mut processed_param) = parse_input_documentation(&input.name, &input.documentation) {
+        processed_param.group = input.group;
+        processed_param.example = Some(input.value).filter(|value| !value.is_empty() && !value.starts_with(['|', '>']));
+        parameters.push(processed_param);
+    } else {
+        diagnostics.push(Diagnostic::new(Severity::Error, "input documentation not recognized; the input is skipped", input.index, &input.text, input.documentation_columns));
+    }
+}
+
+// --- Documentation String Parsing ---
+fn parse_input_documentation(yaml_name: &str, documentation: &str) -> Option<ProcessedParameter> {
+    if !DOC_TYPE_RE.is_match(documentation) {
+        explain(&format!("  '{}': DOC_TYPE_RE did not match; the documentation must start with a type", yaml_name));
+    }
+     DOC_TYPE_RE.captures(documentation).map(|caps| {
+        // --- Extract raw parts clause by clause ---
+        let type_options = caps["Type"].trim().to_string();
+        let mut rest = caps["Rest"].trim();
+        explain(&format!("  '{}': DOC_TYPE_RE matched; Type = '{}'", yaml_name, type_options));
+
+        let mut alias = None;
+        if let Some(caps) = DOC_ALIAS_RE.captures(rest) {
+            explain(&format!("  '{}': DOC_ALIAS_RE matched; Alias = '{}'", yaml_name, &caps["Alias"]));
+            alias = Some(caps["Alias"].to_string());
+            rest = caps.name("Rest").map_or("", |m| m.as_str()).trim();
+        }
+
+        // Inputs documented without a required status are optional
+        let mut required_status = "Optional".to_string();
+        let after_required_status;
+        match split_required_status(rest) {
+            Some((status, remainder)) => {
+                explain(&format!("  '{}': DOC_REQUIRED_RE matched; Required = '{}'", yaml_name, status));
+                required_status = status;
+                after_required_status = remainder;
+                rest = after_required_status.trim();
+            }
+            None => explain(&format!("  '{}': DOC_REQUIRED_RE did not match; assuming Optional", yaml_name)),
+        }
+
+        let mut allowed_values = Vec::new();
+        let without_allowed_values;
+        if let Some(caps) = DOC_ALLOWED_VALUES_RE.captures(rest) {
+            explain(&format!("  '{}': DOC_ALLOWED_VALUES_RE matched; Values = '{}'", yaml_name, &caps["Values"]));
+            allowed_values = split_allowed_values(&caps["Values"]);
+            let clause = caps.get(0).map_or(0..0, |m| m.range());
+            without_allowed_values = format!("{} {}", &rest[..clause.start], &rest[clause.end..]);
+            rest = without_allowed_values.trim();
+        }
+
+        let mut default_value_str = None;
+        if let Some(caps) = DOC_DEFAULT_RE.captures(rest) {
+            explain(&format!("  '{}': DOC_DEFAULT_RE matched; Default = '{}'", yaml_name, caps["Default"].trim()));
+            default_value_str = Some(caps["Default"].trim().to_string());
+            rest = rest[..caps.get(0).map_or(rest.len(), |m| m.start())].trim();
+        }
+
+        // Whatever is left between the required status and the default
+        let description = rest.strip_suffix('.').unwrap_or(rest).trim().to_string();
+        let final_description = if description.is_empty() { yaml_name.to_string() } else { description };
+        explain(&format!("  '{}': description = '{}'", yaml_name, final_description));
+
+
+        // --- Process extracted parts ---
+        let mut enum_options = None;
+        let mut base_csharp_type = "string".to_string(); // Default assumption
+
+        let union = union_type(&type_options);
+        let type_reason = if let Some((union_base_type, _)) = &union {
+            base_csharp_type = union_base_type.clone();
+            "a union of types (see --union-types)"
+        // Quoted or not, any other pipe-separated list of atoms is a list of options
+        } else if type_options.contains('|') {
+            enum_options = Some(type_options.split('|').map(|s| s.trim().replace('\'', "")).collect());
+            base_csharp_type = csharp_identifier(yaml_name); // Assume enum type name matches PascalCase property name
+            "a list of options"
+        } else if type_options == "boolean" {
+            base_csharp_type = "bool".to_string();
+            "documented as boolean"
+        } else if type_options == "int" || type_options == "integer" {
+            base_csharp_type = "int".to_string();
+            "documented as an integer"
+        } else if allowed_values.len() > 1 {
+            enum_options = Some(allowed_values);
+            base_csharp_type = csharp_identifier(yaml_name);
+            "an Allowed values list"
+        } else if type_options == "string" && !ARGS.no_int_inference && !ARGS.no_int_inference_for.iter().any(|n| n == yaml_name) {
+            // Fallback for pages that document numbers as strings: if we see this as a string, and it
+            // has a default value, try to parse the default value as an int.
+            // If it parses, set the type to int, otherwise keep it as a string.
+            if let Some(default_value) = &default_value_str
+                && default_value.parse::<i32>().is_ok()
+            {
+                base_csharp_type = "int".to_string();
+                "a string with a whole number default (see --no-int-inference)"
+            } else {
+                "a string"
+            }
+        } else {
+            "no more specific rule applies"
+        }; // Add other types like 'object', 'secureFile' etc. if needed
+        explain(&format!("  '{}': type {}, as {}", yaml_name, base_csharp_type, type_reason));
+
+        let mut parameter = build_parameter(yaml_name, final_description, base_csharp_type, enum_options, &required_status, default_value_str);
+        parameter.input_kind = InputKind::from_docs(&type_options, yaml_name, alias.as_deref());
+        parameter.aliases = alias.into_iter().collect();
+        parameter.visible_when = DOC_USE_WHEN_RE.captures(&parameter.description).map(|caps| caps["Condition"].trim().to_string());
+        parameter.union_types = union.map(|(_, types)| types).unwrap_or_default();
+        parameter
+    })
+}
+
+// The required status a documentation clause starts with (Required, Optional or "Required when
+// ..."), whatever its phrasing, and the rest of the documentation after it
+fn split_required_status(text: &str) -> Option<(String, String)> {
+    let text = text.trim();
+    let text = match REQUIRED_CONDITION_RE.find(text) {
+        Some(condition) => format!("Required when {}", &text[condition.end()..]),
+        None => match REQUIRED_PHRASING_RE.captures(text) {
+            Some(caps) => {
+                let status = match caps["Status"].to_lowercase().as_str() {
+                    "optional" | "not required" => "Optional",
+                    _ => "Required",
+                };
+                format!("{}. {}", status, &text[caps.get(0).map_or(0, |m| m.end())..])
+            }
+            None => text.to_string(),
+        },
+    };
+
+    DOC_REQUIRED_RE.captures(&text).map(|caps| (
+        caps["Required"].trim().to_string(),
+        caps.name("Rest").map_or("", |m| m.as_str()).trim().to_string(),
+    ))
+}
+
+// A type documented as a union of types, e.g. 'string or boolean' or 'string | boolean': the C#
+// type --union-types maps it to, and the types it lists
+fn union_type(input_type: &str) -> Option<(String, Vec<String>)> {
+    const TYPES: &[&str] = &["string", "boolean", "bool", "int", "integer", "number", "object", "filePath"];
+    let types: Vec<String> = input_type.split('|')
+        .flat_map(|t| t.split(" or "))
+        .map(|t| t.trim().to_string())
+        .collect();
+    if types.len() < 2 || !types.iter().all(|t| TYPES.contains(&t.as_str())) {
+        return None;
+    }
+
+    let base_csharp_type = match (ARGS.union_types, types[0].as_str()) {
+        (UnionTypes::First, "boolean" | "bool") => "bool",
+        (UnionTypes::First, "int" | "integer") => "int",
+        _ => "string",
+    };
+    Some((base_csharp_type.to_string(), types))
+}
+
+// The options of an "Allowed values: a, b (Display name), or c" list
+fn split_allowed_values(values: &str) -> Vec<String> {
+    values.split(',')
+        .map(|value| value.trim())
+        .map(|value| value.strip_prefix("or ").or_else(|| value.strip_prefix("and ")).unwrap_or(value))
+        .map(|value| value.split_once(" (").map_or(value, |(value, _)| value)) // Drop display names
+        .map(|value| value.trim().trim_matches(|c| c == '\'' || c == '`' || c == '"').to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+// Applies the nullability and default rules shared by every input source
+fn build_parameter(
+    yaml_name: &str,
+    description: String,
+    base_csharp_type: String,
+    enum_options: Option<Vec<String>>,
+    required_status: &str, // Required, Optional, Required when...
+    default_value_str: Option<String>
+) -> ProcessedParameter {
+    let csharp_name = csharp_identifier(yaml_name);
+    let description = normalize_text(&description);
+
+    // An input defaulting to a pipeline macro or glob takes strings, whatever its documented type
+    let (base_csharp_type, enum_options) = match &default_value_str {
+        Some(default_value) if base_csharp_type != "string" && is_non_literal_default(default_value) => {
+            print_diagnostic(&format!("// '{}' defaults to '{}', so it is generated as a string", yaml_name, default_value));
+            explain(&format!("  '{}': type string instead of {}, as the default '{}' is not a literal", yaml_name, base_csharp_type, default_value));
+            ("string".to_string(), None)
+        }
+        _ => (base_csharp_type, enum_options),
+    };
+    let is_conditionally_required = required_status.starts_with("Required when");
+    let is_optional = required_status == "Optional";
+
+    // Apply Nullability Rule (Rule #1)
+    let is_nullable = (is_optional || is_conditionally_required || base_csharp_type == "string") && default_value_str.is_none();
+
+    let csharp_type = if is_nullable {
+        format!("{}?", base_csharp_type)
+    } else {
+        base_csharp_type.clone()
+    };
+    explain(&format!(
+        "  '{}': {} ({}, {})",
+        yaml_name,
+        if is_nullable { "nullable" } else { "not nullable" },
+        required_status,
+        if default_value_str.is_some() { "has a default" } else { "no default" },
+    ));
+
+    // Format Default Arg for Getter (Rule #2)
+    let getter_default_arg = default_value_str.as_ref()
+        .filter(|_| !is_nullable)
+        .map(|default_value| format_default_value(
+            default_value,
+            &base_csharp_type,
+            enum_options.is_some() // is_enum
+        ));
+    if let (Some(default_value), Some(default_arg)) = (&default_value_str, &getter_default_arg) {
+        explain(&format!("  '{}': default '{}' becomes {}", yaml_name, default_value, default_arg));
+    }
+
+    ProcessedParameter {
+        yaml_name: yaml_name.to_string(),
+        csharp_name,
+        description,
+        csharp_type,
+        enum_options,
+        enum_labels: Vec::new(),
+        visible_when: None,
+        group: None,
+        required_when: required_status.strip_prefix("Required when").map(|c| c.trim().to_string()),
+        is_required: required_status == "Required",
+        aliases: Vec::new(),
+        help: None,
+        union_types: Vec::new(),
+        is_nullable,
+        getter_default_arg,
+        base_csharp_type,
+        input_kind: InputKind::Plain,
+        example: None,
+    }
+}
+
+// --- Default Value Formatting (mostly same as before) ---
+fn format_default_value(value: &str, base_type: &str, is_enum: bool) -> String {
+    // Pipeline macros and globs (often seen in YAML examples) are never C# literals, so they
+    // are always quoted, whatever the input's type
+    if is_non_literal_default(value) {
+        return csharp_string_literal(value);
+    }
+
+   match base_type {
+       "string" => csharp_string_literal(value),
+       "bool" => match normalized_bool(value) {
+           Some(value) => value.to_string(),
+           None => {
+               eprintln!("Warning: '{}' is not a boolean default; using false.", value);
+               "false".to_string()
+           }
+       },
+       _ if is_enum => {
+           let clean_value = enum_member_name(value.trim_matches('\''));
+           format!("{}.{}", base_type, clean_value)
+       }
+       _ => value.to_string(), // For int, etc.
+   }
+}
+
+
+lazy_static! {
+    static ref CSHARP_KEYWORDS: std::collections::HashSet<&'static str> = [
+        "abstract", "as", "base", "bool", "break", "byte", "case", "catch", "char", "checked", "class",
+        "const", "continue", "decimal", "default", "delegate", "do", "double", "else", "enum", "event",
+        "explicit", "extern", "false", "finally", "fixed", "float", "for", "foreach", "goto", "if",
+        "implicit", "in", "int", "interface", "internal", "is", "lock", "long", "namespace", "new",
+        "null", "object", "operator", "out", "override", "params", "private", "protected", "public",
+        "readonly", "ref", "return", "sbyte", "sealed", "short", "sizeof", "stackalloc", "static",
+        "string", "struct", "switch", "this", "throw", "true", "try", "typeof", "uint", "ulong",
+        "unchecked", "unsafe", "ushort", "using", "virtual", "void", "volatile", "while",
+    ].into_iter().collect();
+}
+
+// Whether a name can be used as is for a C# type or namespace part
+fn is_csharp_identifier(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_') && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+// A valid C# identifier for a name from the docs: PascalCased, prefixed with '_' if it would
+// start with a digit, and escaped with '@' if it is still a keyword
+fn csharp_identifier(name: &str) -> String {
+    let identifier = name.to_pascal_case();
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", identifier)
+    } else if CSHARP_KEYWORDS.contains(identifier.as_str()) {
+        format!("@{}", identifier)
+    } else {
+        identifier
+    }
+}
+
+// A valid enum member name for an option. Options such as '4.x' or '3.1' would lose their
+// separators when PascalCased ('4X', '31') and start with a digit, so they keep them as '_'
+// instead: '_4_x', '_3_1'. The original option stays in the member's YamlMember alias.
+fn enum_member_name(option: &str) -> String {
+    if option.starts_with(|c: char| c.is_ascii_digit()) {
+        let sanitized: String = option.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect();
+        return format!("_{}", sanitized);
+    }
+    csharp_identifier(option)
+}
+
+// "true" or "false" for any casing of a boolean, quoted or not, e.g. 'True' or FALSE
+fn normalized_bool(value: &str) -> Option<&'static str> {
+    let value = value.trim().trim_matches(|c| c == '\'' || c == '"').trim();
+    if value.eq_ignore_ascii_case("true") {
+        Some("true")
+    } else if value.eq_ignore_ascii_case("false") {
+        Some("false")
+    } else {
+        None
+    }
+}
+
+// A C# string literal of the value. Paths with backslashes (e.g. $(Agent.TempDirectory)\*.log)
+// read best as verbatim strings; anything else is escaped.
+fn csharp_string_literal(value: &str) -> String {
+    if value.contains('\\') {
+        return format!("@\"{}\"", value.replace('"', "\"\""));
+    }
+
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_control() => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+// Defaults that only mean something to the pipeline: macros such as $(Build.SourcesDirectory),
+// template expressions such as ${{ parameters.x }} or $[ variables.x ], and globs such as **/*.csproj
+fn is_non_literal_default(value: &str) -> bool {
+    ["$(", "${{", "$[", "*", "?"].iter().any(|syntax| value.contains(syntax))
+}
+
+// --- C# Code Generation ---
+// Class-level remark pointing at the replacement of a deprecated task
+fn deprecation_remark(deprecation: &Deprecation, task_name: &str, task_version: &str) -> String {
+    match (&deprecation.replacement_class, &deprecation.replacement) {
+        (Some(class), _) => format!("{}@{} is deprecated; use <see cref=\"{}\"/> instead.", task_name, task_version, class),
+        (None, Some(r)) => format!("{}@{} is deprecated; use {}@{} instead.", task_name, task_version, r.task_name, r.task_version),
+        (None, None) => format!("{}@{} is deprecated.", task_name, task_version),
+    }
+}
+
+fn generate_csharp(
+    task: &TaskDefinition,
+    base_class: &str,
+    accessors: &AccessorSurface,
+    templates: &CodeTemplates
+) -> Result<GeneratedTask, Box<dyn std::error::Error>> {
+    let task_summary = &task.info.task_summary;
+    let task_name = &task.info.task_name;
+    let task_version = &task.info.task_version;
+    let params = &task.info.parameters;
+    let class_name = &task.class_name;
+    let mut enums = Vec::new();
+    let mut properties_code = String::new();
+    let mut properties = Vec::new();
+
+    // Everything below is emitted in documentation order: enums in the order their
+    // parameters appear, enum members in the order their options are listed, and
+    // properties in the order of the inputs in the snippet.
+
+    // --- Generate Enums ---
+    for p in params {
+        if let Some(options) = &p.enum_options {
+            let enum_code = templates.render_enum(&EnumContext {
+                name: &p.base_csharp_type,
+                yaml_name: &p.yaml_name,
+                options: options.iter().enumerate()
+                    .map(|(index, option)| EnumOptionContext {
+                        name: enum_member_name(option),
+                        alias: option.replace('\'', ""),
+                        label: p.enum_labels.get(index).filter(|l| !l.is_empty() && *l != option).map(|l| documentation_escaped(l)),
+                    })
+                    .collect(),
+            })?;
+            enums.push(GeneratedEnum {
+                name: p.base_csharp_type.clone(),
+                code: enum_code,
+                usings: vec!["YamlDotNet.Serialization".to_string()],
+            });
+        }
+     }
+
+    // The filePath wrapper is emitted with the enums, so merged output de-duplicates it the same way
+    let file_path_type = ARGS.file_path_type.as_deref().filter(|_| params.iter().any(|p| p.input_kind == InputKind::FilePath));
+    if let Some(file_path_type) = file_path_type {
+        enums.push(GeneratedEnum {
+            name: file_path_type.to_string(),
+            code: file_path_wrapper(file_path_type),
+            usings: vec!["System.Diagnostics.CodeAnalysis".to_string()],
+        });
+    }
+    let service_connection_type = params.iter().find_map(service_connection_type);
+    if let Some(service_connection_type) = service_connection_type {
+        enums.push(GeneratedEnum {
+            name: service_connection_type.to_string(),
+            code: service_connection_wrapper(service_connection_type),
+            usings: vec!["System.Diagnostics.CodeAnalysis".to_string()],
+        });
+    }
+
+    let renamed_params = member_parameters(task);
+    let params = &renamed_params;
+
+    // --- Generate Properties ---
+    // Inputs listed under a section (a task.json group, or a section comment in the snippet) go
+    // in a #region per section, after the ungrouped inputs
+    let mut groups: Vec<Option<&str>> = vec![None];
+    for p in params {
+        if !groups.contains(&p.group.as_deref()) {
+            groups.push(p.group.as_deref());
+        }
+    }
+    for group in groups {
+        let group_params: Vec<&ProcessedParameter> = params.iter().filter(|p| p.group.as_deref() == group).collect();
+        if let Some(group) = group {
+            properties_code.push_str(&format!("    #region {}\n\n", group));
+        }
+        for p in group_params {
+            let mut property = property_context(p, accessors);
+            property.code = templates.render_property(&property)?;
+            properties_code.push_str(&property.code);
+            properties.push(property);
+        }
+        if group.is_some() {
+            properties_code.push_str("    #endregion\n\n");
+        }
+    }
+
+    // --- Assemble Final Class ---
+    // What the base constructor is given: the 'uses:' of an action, 'Name@Version' of a task
+    let reference = match &task.action {
+        Some(action) => action.uses.clone(),
+        None => format!("{}@{}", task_name, task_version),
+    };
+    let source = match &task.action {
+        Some(action) => format!("GitHub Action: {} ({})", documentation_escaped(&action.name), action.uses),
+        None => format!("Azure DevOps task: {} v{}", task_name, task_version),
+    };
+    let class_summary = format!(
+        "Generated C# model for the {source}.\n/// {task_summary}",
+        source = source,
+        task_summary = documentation_escaped(task_summary) // Already trimmed
+    );
+    let escaped_class_summary = class_summary.lines()
+         .map(|l| format!("/// {}", l))
+         .collect::<Vec<_>>()
+         .join("\n");
+
+    let mut usings = vec!["YamlDotNet.Serialization".to_string()];
+    if task.action.is_none() {
+        usings.insert(0, "Sharpliner.AzureDevOps.Tasks".to_string());
+    }
+    if file_path_type.is_some() || service_connection_type.is_some() {
+        usings.push("System.Diagnostics.CodeAnalysis".to_string());
+    }
+    if !task.steps.is_empty() {
+        let string_accessor = accessors.resolve(AccessorKind::String).map_or("GetString", |a| a.name.as_str());
+        properties_code.push_str(&task_group::steps_code(&task.steps, params, string_accessor));
+        usings.push("System".to_string());
+    }
+    if ARGS.validate {
+        properties_code.push_str(&validate_method(task_name, task_version, params, accessors));
+        usings.extend(["System".to_string(), "System.Collections.Generic".to_string()]);
+    }
+    let mut class_attributes = String::new();
+    let remarks: Vec<String> = task.deprecation.iter()
+        .map(|deprecation| deprecation_remark(deprecation, task_name, task_version))
+        .chain(task.remarks.iter().cloned())
+        .collect();
+    match remarks.as_slice() {
+        [] => {}
+        [remark] => class_attributes.push_str(&format!("/// <remarks>\n/// {}\n/// </remarks>\n", remark)),
+        remarks => class_attributes.push_str(&format!(
+            "/// <remarks>\n{}\n/// </remarks>\n",
+            remarks.iter().map(|r| format!("/// <para>{}</para>", r)).collect::<Vec<_>>().join("\n")
+        )),
+    }
+    for example in &task.examples {
+        let code = example.lines()
+            .map(|l| format!("/// {}", documentation_escaped(l)).trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        class_attributes.push_str(&format!("/// <example>\n/// <code lang=\"yaml\">\n{}\n/// </code>\n/// </example>\n", code));
+    }
+    if let Some(deprecation) = &task.deprecation {
+        usings.push("System".to_string());
+        class_attributes.push_str(&format!(
+            "[Obsolete(\"{}\")]\n",
+            deprecation.obsolete_message(task_name, task_version).replace('"', "\\\"")
+        ));
+    }
+
+    // Tasks without inputs or output variables are just the constructor
+    let members_code = format!("{}{}", properties_code.trim_end(), outputs_class(&task.output_variables));
+    let members_code = match members_code.trim_start_matches('\n') {
+        "" => String::new(),
+        members => format!("{}\n", members),
+    };
+
+    let mut class_code = templates.render_class(&ClassContext {
+        task_name,
+        task_version,
+        reference: &reference,
+        class_name,
+        base_class,
+        documentation_url: &task.documentation_url,
+        description: documentation_escaped(task_summary),
+        partial: ARGS.partial,
+        obsolete_message: task.deprecation.as_ref().map(|d| d.obsolete_message(task_name, task_version).replace('"', "\\\"")),
+        remarks,
+        examples: &task.examples,
+        task_id: task.task_id.as_deref(),
+        default_display_name: task.instance_name_format.as_deref(),
+        demands: &task.requirements.demands,
+        minimum_agent_version: task.requirements.minimum_agent_version.as_deref(),
+        properties,
+        outputs: task.output_variables.iter().map(|v| OutputContext { name: &v.name, description: &v.description }).collect(),
+        summary: escaped_class_summary,
+        attributes: class_attributes,
+        constants: class_constants(task),
+        members: members_code,
+    })?;
+    if let Some(factory_class) = &ARGS.step_factories {
+        class_code.push('\n');
+        class_code.push_str(&step_factories::step_factory(factory_class, class_name, task_summary, params));
+    }
+
+    Ok(GeneratedTask {
+        class_name: class_name.to_string(),
+        task_name: task_name.to_string(),
+        task_version: task_version.to_string(),
+        documentation_url: task.documentation_url.clone(),
+        summary: documentation_escaped(task_summary),
+        usings,
+        enums,
+        class_code,
+    })
+}
+
+// What a task's class derives from: --action-base-class for actions, --base-class otherwise
+fn base_class(task: &TaskDefinition) -> &str {
+    match task.action {
+        Some(_) => &ARGS.action_base_class,
+        None => &ARGS.base_class,
+    }
+}
+
+// The task's parameters with their final property names. Properties can't share a name with
+// their class or the members generated next to them, so those get an 'Input' suffix. run() stores
+// these on the task before anything is emitted, so the manifest names the properties the classes have.
+fn member_parameters(task: &TaskDefinition) -> Vec<ProcessedParameter> {
+    let reserved_names = [task.class_name.as_str(), "Outputs", "Demands", "MinimumAgentVersion", "DefaultDisplayName", "TaskId"].into_iter()
+        .chain(ARGS.validate.then_some("Validate"))
+        .chain(["Steps", "Expand"].into_iter().filter(|_| !task.steps.is_empty()))
+        .collect::<Vec<_>>();
+    task.info.parameters.iter()
+        .map(|p| {
+            let mut p = p.clone();
+            if reserved_names.contains(&p.csharp_name.as_str()) {
+                let renamed = format!("{}Input", p.csharp_name);
+                print_diagnostic(&format!("// Property '{}' of '{}' is named '{}' to avoid a clash", p.csharp_name, p.yaml_name, renamed));
+                p.csharp_name = renamed;
+            }
+            p
+        })
+        .collect()
+}
+
+// The documentation, attributes and accessors of the property for one input
+fn property_context<'a>(p: &'a ProcessedParameter, accessors: &AccessorSurface) -> PropertyContext<'a> {
+    let mut docs = String::new();
+    let mut attributes = String::new();
+    let mut description_lines = p.description.lines()
+        .map(|l| format!("    /// {}", documentation_escaped(l.trim())))
+        .collect::<Vec<_>>()
+        .join("\n");
+     // Add the original documentation string as well for reference
+     
+     if ARGS.include_original_documentation
+     {
+        let doc_comment_line = format!("    /// Raw Doc: {}", documentation_escaped(&p.description)); // Need helper to escape XML chars
+        description_lines.push_str(&format!("\n{}", doc_comment_line));
+     }
+
+
+    docs.push_str(&format!("    /// <summary>\n{}\n    /// </summary>\n", description_lines));
+    let remarks: Vec<String> = p.help.clone().into_iter()
+        .chain((!p.union_types.is_empty()).then(|| format!("Accepts any of these types: {}.", p.union_types.join(", "))))
+        .chain(p.input_kind.remark())
+        .chain(p.required_when.iter().map(|c| format!("Required when {}.", documentation_escaped(c))))
+        // The snippet's value is usually the default; it's only worth showing when there isn't one
+        .chain(p.example.iter().filter(|_| p.getter_default_arg.is_none()).map(|e| format!("For example <c>{}</c>.", documentation_escaped(e))))
+        .chain((!p.aliases.is_empty()).then(|| format!(
+            "Also accepted in YAML as {}.",
+            p.aliases.iter().map(|a| format!("<c>{}</c>", a)).collect::<Vec<_>>().join(", ")
+        )))
+        .collect();
+    match remarks.as_slice() {
+        [] => {}
+        [remark] => docs.push_str(&format!("    /// <remarks>{}</remarks>\n", remark)),
+        remarks => docs.push_str(&format!(
+            "    /// <remarks>\n{}\n    /// </remarks>\n",
+            remarks.iter().map(|r| format!("    /// <para>{}</para>", r)).collect::<Vec<_>>().join("\n")
+        )),
+    }
+    if ARGS.condition_attributes {
+        if let Some(condition) = &p.visible_when {
+            attributes.push_str(&format!("    [VisibleWhen(\"{}\")]\n", condition.replace('"', "\\\"")));
+        }
+        if let Some(condition) = &p.required_when {
+            attributes.push_str(&required_when_attributes(condition));
+        }
+    }
+    attributes.push_str("    [YamlIgnore]\n");
+
+    // filePath inputs stay strings underneath; the wrapper converts to and from them implicitly
+    let file_path_type = ARGS.file_path_type.as_deref().filter(|_| p.input_kind == InputKind::FilePath);
+    let csharp_type = match file_path_type.or(service_connection_type(p)) {
+        Some(wrapper_type) if p.is_nullable => format!("{}?", wrapper_type),
+        Some(wrapper_type) => wrapper_type.to_string(),
+        None => p.csharp_type.clone(),
+    };
+    let value = match (file_path_type, service_connection_type(p)) {
+        (Some(_), _) => "value?.Value",
+        (_, Some(_)) => "value?.Name",
+        _ => "value",
+    };
+    PropertyContext {
+        yaml_name: &p.yaml_name,
+        name: &p.csharp_name,
+        type_name: csharp_type,
+        description: &p.description,
+        nullable: p.is_nullable,
+        required: p.is_required,
+        kind: p.input_kind.name(),
+        default: p.getter_default_arg.as_deref(),
+        example: p.example.as_deref(),
+        required_when: p.required_when.as_deref(),
+        visible_when: p.visible_when.as_deref(),
+        options: p.enum_options.as_deref(),
+        labels: &p.enum_labels,
+        aliases: &p.aliases,
+        group: p.group.as_deref(),
+        docs,
+        attributes,
+        getter: getter_expression(p, accessors),
+        value: value.to_string(),
+        code: String::new(),
+    }
+}
+
+// [RequiredWhen("command", "push")] per alternative of a condition made of 'input = value'
+// comparisons joined by '||'; any other condition is kept whole, [RequiredWhen("...")]
+fn required_when_attributes(condition: &str) -> String {
+    match condition_comparisons(condition) {
+        Some(comparisons) => comparisons.iter()
+            .map(|(input, value)| format!("    [RequiredWhen(\"{}\", \"{}\")]\n", input, value))
+            .collect(),
+        None => format!("    [RequiredWhen(\"{}\")]\n", condition.replace('"', "\\\"")),
+    }
+}
+
+// The 'input = value' comparisons of a condition made only of such comparisons joined by '||'
+fn condition_comparisons(condition: &str) -> Option<Vec<(&str, &str)>> {
+    condition.split("||")
+        .map(|alternative| alternative.split_once('=')
+            .map(|(input, value)| (input.trim(), value.trim()))
+            .filter(|(input, value)| {
+                let is_atom = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || "_.-".contains(c));
+                is_atom(input) && is_atom(value)
+            }))
+        .collect()
+}
+
+// Validate() (--validate), throwing if an input the task requires isn't set. Inputs required under
+// a condition are checked when the condition holds; conditions the generator can't translate to
+// C# are left as a comment.
+fn validate_method(task_name: &str, task_version: &str, params: &[ProcessedParameter], accessors: &AccessorSurface) -> String {
+    // An input is unset when it has no value at all, whatever its type
+    let string_accessor = accessors.resolve(AccessorKind::String).map_or("GetString", |a| a.name.as_str());
+    let mut checks = String::new();
+    for p in params {
+        let is_unset = format!("{}(\"{}\") is null", string_accessor, p.yaml_name);
+        if p.is_required && p.getter_default_arg.is_none() {
+            checks.push_str(&format!("        if ({}) missing.Add(\"{}\");\n", is_unset, p.yaml_name));
+        } else if let Some(condition) = &p.required_when {
+            match condition_expression(condition, params) {
+                Some(holds) => checks.push_str(&format!("        if ({} && {}) missing.Add(\"{}\");\n", holds, is_unset, p.yaml_name)),
+                None => {
+                    print_diagnostic(&format!("// Validate() can't check that '{}' is required when {}", p.yaml_name, condition));
+                    checks.push_str(&format!("        // Not checked: '{}' is required when {}\n", p.yaml_name, condition));
+                }
+            }
+        }
+    }
+
+    format!(
+r#"    /// <summary>
+    /// Checks that the inputs the task requires are set, including those required under a
+    /// condition that holds.
+    /// </summary>
+    /// <exception cref="InvalidOperationException">Required inputs are missing.</exception>
+    public void Validate()
+    {{
+        var missing = new List<string>();
+{checks}        if (missing.Count > 0)
+        {{
+            throw new InvalidOperationException($"{task_name}@{task_version} is missing required inputs: {{string.Join(", ", missing)}}");
+        }}
+    }}
+
+"#,
+        checks = checks,
+        task_name = task_name,
+        task_version = task_version,
+    )
+}
+
+// A required-when condition as a C# expression over the properties, e.g. 'command = push' as
+// (Command == Command.Push). Comparing the properties takes the inputs' defaults into account.
+fn condition_expression(condition: &str, params: &[ProcessedParameter]) -> Option<String> {
+    let alternatives = condition_comparisons(condition)?.into_iter()
+        .map(|(input, value)| {
+            let p = params.iter().find(|p| p.yaml_name.eq_ignore_ascii_case(input))?;
+            match (&p.enum_options, p.base_csharp_type.as_str()) {
+                (Some(options), _) => options.iter()
+                    .find(|option| option.replace('\'', "").eq_ignore_ascii_case(value))
+                    .map(|option| format!("{} == {}.{}", p.csharp_name, p.base_csharp_type, enum_member_name(option))),
+                (None, "bool") => normalized_bool(value).map(|value| format!("{} == {}", p.csharp_name, value)),
+                (None, _) => Some(format!("string.Equals(Convert.ToString({}), \"{}\", StringComparison.OrdinalIgnoreCase)", p.csharp_name, value)),
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("({})", alternatives.join(" || ")))
+}
+
+// Class-level constants describing the task as a whole
+fn class_constants(task: &TaskDefinition) -> String {
+    let requirements = &task.requirements;
+    let mut code = String::new();
+    if let Some(id) = &task.task_id {
+        code.push_str(&format!(
+            "    /// <summary>\n    /// The task's unique id, which tells it apart from same-named tasks of other extensions.\n    /// </summary>\n    public const string TaskId = {};\n\n",
+            csharp_string_literal(id)
+        ));
+    }
+    if let Some(format) = &task.instance_name_format {
+        code.push_str(&format!(
+            "    /// <summary>\n    /// The display name Azure DevOps gives steps running the task, with input macros such as $(command).\n    /// </summary>\n    public const string DefaultDisplayName = {};\n\n",
+            csharp_string_literal(format)
+        ));
+    }
+    if !requirements.demands.is_empty() {
+        let demands = requirements.demands.iter()
+            .map(|d| csharp_string_literal(d))
+            .collect::<Vec<_>>()
+            .join(", ");
+        code.push_str(&format!(
+            "    /// <summary>\n    /// Capabilities a self-hosted agent must have to run the task.\n    /// </summary>\n    public static readonly string[] Demands = new[] {{ {} }};\n\n",
+            demands
+        ));
+    }
+    if let Some(version) = &requirements.minimum_agent_version {
+        code.push_str(&format!(
+            "    /// <summary>\n    /// The oldest agent version that can run the task.\n    /// </summary>\n    public const string MinimumAgentVersion = {};\n\n",
+            csharp_string_literal(version)
+        ));
+    }
+    code
+}
+
+// Nested class of references to the task's output variables, e.g. Outputs.NuGetToolPath("nuget")
+// for $(nuget.NuGetToolPath), the reference from a later step to the step named 'nuget'
+fn outputs_class(variables: &[OutputVariable]) -> String {
+    if variables.is_empty() {
+        return String::new();
+    }
+
+    let methods = variables.iter()
+        .map(|v| {
+            let description = if v.description.is_empty() { v.name.clone() } else { documentation_escaped(&v.description) };
+            format!(
+r#"        /// <summary>
+        /// {description}
+        /// </summary>
+        /// <param name="stepName">The name of the step running the task, if it has one.</param>
+        public static string {method_name}(string? stepName = null) => stepName is null ? "$({name})" : $"$({{stepName}}.{name})";"#,
+                description = description,
+                method_name = csharp_identifier(&v.name),
+                name = v.name,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    format!(
+r#"
+
+    /// <summary>
+    /// References to the output variables the task sets, for use in later steps.
+    /// </summary>
+    public static class Outputs {{
+{methods}
+    }}"#,
+        methods = methods,
+    )
+}
+
+// A string-backed wrapper type for filePath inputs
+fn file_path_wrapper(type_name: &str) -> String {
+    format!(
+r#"/// <summary>
+/// A path to a file or folder, as given to a filePath task input.
+/// </summary>
+public sealed record class {type_name}(string Value) {{
+    [return: NotNullIfNotNull(nameof(value))]
+    public static implicit operator {type_name}?(string? value) => value is null ? null : new(value);
+
+    public static implicit operator string({type_name} path) => path.Value;
+
+    public override string ToString() => Value;
+}}"#,
+        type_name = type_name,
+    )
+}
+
+// The --service-connection-type wrapper, for service connection inputs
+fn service_connection_type(p: &ProcessedParameter) -> Option<&'static str> {
+    ARGS.service_connection_type.as_deref().filter(|_| matches!(p.input_kind, InputKind::ServiceConnection(_)))
+}
+
+// A string-backed wrapper type for service connection inputs. There's deliberately no implicit
+// conversion from string, so a path or other text can't be passed where a connection is expected.
+fn service_connection_wrapper(type_name: &str) -> String {
+    format!(
+r#"/// <summary>
+/// The name of a service connection, as given to a service connection task input.
+/// </summary>
+public sealed record class {type_name}(string Name) {{
+    [return: NotNullIfNotNull(nameof(name))]
+    public static {type_name}? From(string? name) => name is null ? null : new(name);
+
+    public static implicit operator string({type_name} connection) => connection.Name;
+
+    public override string ToString() => Name;
+}}"#,
+        type_name = type_name,
+    )
+}
+
+// Builds the getter expression for a property, using whichever accessor the base class offers
+fn getter_expression(p: &ProcessedParameter, accessors: &AccessorSurface) -> String {
+    let getter = accessor_expression(p, accessors);
+    match service_connection_type(p) {
+        Some(service_connection_type) => format!("{}.From({})", service_connection_type, getter),
+        None => getter,
+    }
+}
+
+// The call of the base class accessor reading an input's value
+fn accessor_expression(p: &ProcessedParameter, accessors: &AccessorSurface) -> String {
+    let kind = match p.base_csharp_type.as_str() {
+        "string" if p.input_kind == InputKind::SecureFile => AccessorKind::SecureFile,
+        "string" if matches!(p.input_kind, InputKind::ServiceConnection(_)) => AccessorKind::ServiceConnection,
+        "string" => AccessorKind::String,
+        "bool" => AccessorKind::Bool,
+        "int" => AccessorKind::Int,
+        _ if p.getter_default_arg.is_some() => AccessorKind::Enum, // Assume Enum
+        _ => AccessorKind::NullableEnum,
+    };
+
+    let (accessor, takes_default) = match accessors.resolve(kind) {
+        Some(a) => (a.name.clone(), a.takes_default),
+        None => {
+            let fallback = AccessorSurface::sharpliner_default().resolve(kind).cloned().expect("Default accessor surface is incomplete");
+            eprintln!(
+                "Warning: The base class source does not declare an accessor for {:?} values; '{}' will use '{}', which may not compile.",
+                kind, p.yaml_name, fallback.name
+            );
+            (fallback.name, fallback.takes_default)
+        }
+    };
+
+    let name = &p.yaml_name;
+    match (kind, &p.getter_default_arg) {
+        (AccessorKind::String | AccessorKind::SecureFile | AccessorKind::ServiceConnection, Some(d)) if takes_default => format!("{accessor}(\"{name}\", {d})!"),
+        (AccessorKind::Int, Some(d)) if takes_default => format!("{accessor}(\"{name}\", {d})!.Value"),
+        (_, Some(d)) if takes_default => format!("{accessor}(\"{name}\", {d})"),
+        (AccessorKind::Enum, Some(d)) => format!("{accessor}<{}>(\"{name}\") ?? {d}", p.base_csharp_type),
+        (_, Some(d)) => format!("{accessor}(\"{name}\") ?? {d}"),
+        (AccessorKind::Int, None) => format!("{accessor}(\"{name}\")!.Value"),
+        (AccessorKind::NullableEnum, None) if accessors.verified => format!("{accessor}<{}>(\"{name}\")", p.base_csharp_type),
+        (AccessorKind::NullableEnum, None) => format!("{accessor}<{}>(\"{name}\") /* TODO: Verify GetNullableEnum */", p.base_csharp_type),
+        (_, None) => format!("{accessor}(\"{name}\")"),
+    }
+}
+
+// Helper to escape XML characters in documentation comments
+// Docs text as plain Unicode: non-breaking and other special spaces become plain spaces, and
+// invisible characters (zero-width spaces, byte order marks) that would end up in identifiers
+// or doc comments are dropped
+fn normalize_text(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .chars()
+        .filter(|c| !matches!(c, '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{2060}' | '\u{feff}'))
+        .map(|c| if c != '\n' && c.is_whitespace() { ' ' } else { c })
+        .collect()
+}
+
+fn documentation_escaped(doc: &str) -> String {
+     doc.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        // Add other replacements if needed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NPM_SNIPPET: &str = "# Npm v1
+# Install and publish npm packages, or run an npm command.
+- task: Npm@1
+  inputs:
+    command: 'install' # 'ci' | 'install' | 'publish' | 'custom'. Required. Command. Default: install.
+    workingDir: # string. Working folder that contains package.json.
+    verbose: # boolean. Optional. Use when command = install || command = ci. Verbose logging.
+    customRegistry: 'useNpmrc' # 'useNpmrc' | 'useFeed'. Use when command = install. Registries to use. Default: useNpmrc.
+";
+
+    fn generate(snippet: &str) -> GeneratedTask {
+        generate_with(snippet, &CodeTemplates::builtin())
+    }
+
+    // An empty directory of its own for one test
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}-{}", env!("CARGO_PKG_NAME"), name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn generate_with(snippet: &str, templates: &CodeTemplates) -> GeneratedTask {
+        generate_csharp(&task_from(snippet), "AzureDevOpsTask", &AccessorSurface::sharpliner_default(), templates).unwrap()
+    }
+
+    // NpmTask and an identical AnotherTask, for what is shared between generated classes
+    fn two_tasks() -> [GeneratedTask; 2] {
+        let first = generate(NPM_SNIPPET);
+        let mut second = first.clone();
+        second.class_name = "AnotherTask".to_string();
+        [first, second]
+    }
+
+    pub(crate) fn task_from(snippet: &str) -> TaskDefinition {
+        let info = parse_yaml_lines(snippet).unwrap();
+        TaskDefinition {
+            class_name: info.task_name.to_pascal_case() + "Task",
+            info,
+            documentation_url: "https://example.invalid/npm-v1".to_string(),
+            deprecation: None,
+            other_versions: Vec::new(),
+            output_variables: Vec::new(),
+            requirements: TaskRequirements::default(),
+            instance_name_format: None,
+            task_id: None,
+            remarks: Vec::new(),
+            examples: Vec::new(),
+            steps: Vec::new(),
+            source_hash: cache::hash(snippet),
+            action: None,
+        }
+    }
+
+    #[test]
+    fn versions_of_a_task_get_their_own_class_names() {
+        let mut tasks = [task_from(NPM_SNIPPET), task_from(&NPM_SNIPPET.replace("Npm@1", "Npm@2"))];
+        disambiguate_class_names(&mut tasks);
+
+        assert_eq!(tasks[0].class_name, "Npm1Task");
+        assert_eq!(tasks[1].class_name, "Npm2Task");
+        assert_eq!(tasks[0].remarks, ["A newer version of the task, v2, is generated as <see cref=\"Npm2Task\"/>."]);
+        assert_eq!(tasks[1].remarks, ["The latest version of the task generated; older versions are generated as <see cref=\"Npm1Task\"/>."]);
+    }
+
+    #[test]
+    fn generation_is_deterministic() {
+        let first = generate(NPM_SNIPPET);
+        let second = generate(NPM_SNIPPET);
+        assert_eq!(first.to_file(&CodeTemplates::builtin()).unwrap().as_bytes(), second.to_file(&CodeTemplates::builtin()).unwrap().as_bytes());
+
+        let [first, other] = two_tasks();
+        let merged = output::merge_tasks(&[first.clone(), other.clone()], &CodeTemplates::builtin()).unwrap();
+        let merged_reversed = output::merge_tasks(&[other, first], &CodeTemplates::builtin()).unwrap();
+        assert_eq!(merged.as_bytes(), merged_reversed.as_bytes());
+    }
+
+    #[test]
+    fn minimal_template_set_leaves_out_documentation() {
+        let templates = CodeTemplates::load(None, Some("minimal")).unwrap();
+        let generated = generate_with(NPM_SNIPPET, &templates);
+        let default = generate(NPM_SNIPPET);
+
+        assert!(!generated.class_code.contains("///"));
+        assert!(generated.class_code.starts_with("public record class NpmTask : AzureDevOpsTask {\n"));
+        assert!(generated.class_code.contains("    [YamlIgnore]\n    public Command Command {\n"));
+        assert_eq!(generated.enums[0].code, "public enum Command {\n    [YamlMember(Alias = \"ci\")]\n    Ci,\n    [YamlMember(Alias = \"install\")]\n    Install,\n    [YamlMember(Alias = \"publish\")]\n    Publish,\n    [YamlMember(Alias = \"custom\")]\n    Custom,\n}");
+        assert!(default.class_code.contains("/// <summary>"));
+        assert_eq!(errors::exit_code(CodeTemplates::load(None, Some("no-such-set")).err().unwrap().as_ref()), 2);
+
+        let mut task = task_from(NPM_SNIPPET);
+        task.task_id = Some("fe47e961-9fa8-4106-8639-368c022d43ad".to_string());
+        task.output_variables = vec![OutputVariable { name: "NpmVersion".to_string(), description: "The npm version".to_string() }];
+        let generated = generate_csharp(&task, "AzureDevOpsTask", &AccessorSurface::sharpliner_default(), &templates).unwrap();
+        assert!(!generated.class_code.contains("///"));
+        assert!(generated.class_code.contains("    public const string TaskId = \"fe47e961-9fa8-4106-8639-368c022d43ad\";\n"));
+        assert!(generated.class_code.contains("    public static class Outputs {\n        public static string NpmVersion("));
+    }
+
+    #[test]
+    fn file_path_inputs_are_documented_as_paths() {
+        let mut task = task_from(NPM_SNIPPET);
+        task.info.parameters[1].input_kind = InputKind::FilePath; // workingDir
+        let generated = generate(NPM_SNIPPET);
+        let file_path = generate_csharp(&task, "AzureDevOpsTask", &AccessorSurface::sharpliner_default(), &CodeTemplates::builtin()).unwrap();
+        let remark = "    /// <remarks>A path to a file or folder, usually relative to the repository root.</remarks>\n    [YamlIgnore]\n    public string? WorkingDir {";
+        assert!(file_path.class_code.contains(remark), "{}", file_path.class_code);
+        assert!(!generated.class_code.contains("A path to a file or folder"));
+
+        // The --file-path-type wrapper converts implicitly to and from string
+        let wrapper = file_path_wrapper("FilePath");
+        assert!(wrapper.contains("public sealed record class FilePath(string Value) {"));
+        assert!(wrapper.contains("public static implicit operator FilePath?(string? value) => value is null ? null : new(value);"));
+        assert!(wrapper.contains("public static implicit operator string(FilePath path) => path.Value;"));
+    }
+
+    #[test]
+    fn enum_members_are_documented_with_their_labels() {
+        let mut task = task_from(NPM_SNIPPET);
+        // Labels the same as their value document nothing, so are left out
+        task.info.parameters[0].enum_labels = ["ci", "Install packages", "", "Run a custom command"].map(str::to_string).to_vec();
+        let generated = generate_csharp(&task, "AzureDevOpsTask", &AccessorSurface::sharpliner_default(), &CodeTemplates::builtin()).unwrap();
+        let command = &generated.enums[0].code;
+        assert!(command.contains("    [YamlMember(Alias = \"ci\")]\n    Ci,\n"), "{}", command);
+        assert!(command.contains("    /// <summary>\n    /// Install packages\n    /// </summary>\n    [YamlMember(Alias = \"install\")]\n    Install,\n"));
+        assert!(command.contains("\n\n    [YamlMember(Alias = \"publish\")]\n    Publish,\n"));
+        assert!(command.contains("    /// Run a custom command\n"));
+    }
+
+    #[test]
+    fn class_names_follow_the_template() {
+        assert_eq!(default_class_name(None, "DotNetCoreCLI", "2"), "DotNetCoreCliTask");
+        assert_eq!(default_class_name(Some("{TaskName}V{Version}Task"), "DotNetCoreCLI", "2"), "DotNetCoreCliV2Task");
+        assert_eq!(default_class_name(Some("{TaskName}{Version}"), "npm-authenticate", "0"), "NpmAuthenticate0");
+
+        // Templates that can't give a valid class name are rejected before anything is fetched
+        let args = |template: &str| Args::parse_from([env!("CARGO_PKG_NAME"), "--url", "https://example.invalid", "--class-name-template", template]);
+        assert!(check_usage(&args("{TaskName}V{Version}Task")).is_ok());
+        for template in ["{Version}{TaskName}", "{TaskName}-{Version}", "{TaskName}{Major}"] {
+            assert_eq!(errors::exit_code(&check_usage(&args(template)).unwrap_err()), 2, "{}", template);
+        }
+    }
+
+    #[test]
+    fn step_factories_take_inputs_as_parameters() {
+        let mut task = task_from(NPM_SNIPPET);
+        task.info.parameters[2].is_nullable = false; // verbose, now required
+        task.info.parameters[2].csharp_type = "bool".to_string();
+        let factory = step_factories::step_factory("Steps", "NpmTask", &task.info.task_summary, &task.info.parameters);
+
+        assert!(factory.starts_with("public static partial class Steps {\n    /// <summary>\n    /// Creates a <see cref=\"NpmTask\"/> step."));
+        assert!(factory.contains("    /// <param name=\"workingDir\">"));
+        assert!(factory.contains(
+            "    public static NpmTask Npm(\n        bool verbose,\n        Command? command = null,\n        string? workingDir = null,\n        CustomRegistry? customRegistry = null)\n"
+        ));
+        assert!(factory.contains("        var step = new NpmTask() { Verbose = verbose };\n"));
+        assert!(factory.contains("        if (command is not null) step = step with { Command = command.Value };\n"));
+        assert!(factory.contains("        if (workingDir is not null) step = step with { WorkingDir = workingDir };\n"));
+    }
+
+    #[test]
+    fn catalog_has_a_factory_method_per_task() {
+        let catalog = output::catalog_file(&two_tasks(), "GeneratedTasks", &CodeTemplates::builtin()).unwrap();
+        assert!(catalog.contains("// Catalog of 2 generated task(s)\n"));
+        assert!(catalog.contains("public static class GeneratedTasks {\n"));
+        assert!(catalog.contains(
+            "    /// Npm v1: Install and publish npm packages, or run an npm command.\n    /// </summary>\n    public static AnotherTask Another() => new();\n\n"
+        ));
+        assert!(catalog.contains("    public static NpmTask Npm() => new();\n}\n"));
+    }
+
+    #[test]
+    fn validate_checks_required_inputs() {
+        let mut task = task_from(NPM_SNIPPET);
+        task.info.parameters[1].is_required = true; // workingDir
+        task.info.parameters[2].required_when = Some("command = ci || command = install".to_string()); // verbose
+        task.info.parameters[3].required_when = Some("$(Agent.OS) = Linux".to_string()); // customRegistry
+        let method = validate_method("Npm", "1", &task.info.parameters, &AccessorSurface::sharpliner_default());
+
+        assert!(!method.contains("missing.Add(\"command\")")); // Required, but has a default
+        assert!(method.contains("        if (GetString(\"workingDir\") is null) missing.Add(\"workingDir\");\n"));
+        assert!(method.contains(
+            "        if ((Command == Command.Ci || Command == Command.Install) && GetString(\"verbose\") is null) missing.Add(\"verbose\");\n"
+        ));
+        assert!(method.contains("        // Not checked: 'customRegistry' is required when $(Agent.OS) = Linux\n"));
+        assert!(method.contains("throw new InvalidOperationException($\"Npm@1 is missing required inputs: {string.Join(\", \", missing)}\");"));
+    }
+
+    #[test]
+    fn retries_back_off_with_bounded_jitter() {
+        for attempt in 1..=5 {
+            let backoff = std::time::Duration::from_millis(500) * 2u32.pow(attempt - 1);
+            let delay = http::retry_delay(attempt);
+            assert!(delay >= backoff && delay < backoff.mul_f64(1.5), "attempt {}: {:?}", attempt, delay);
+        }
+    }
+
+    #[test]
+    fn retries_give_up_after_the_limit() {
+        assert!(http::should_retry(true, 0, 3));
+        assert!(http::should_retry(true, 2, 3));
+        assert!(!http::should_retry(true, 3, 3));
+        assert!(!http::should_retry(true, 0, 0));
+        assert!(!http::should_retry(false, 0, 3));
+    }
+
+    #[test]
+    fn enums_can_be_split_into_their_own_files() {
+        let tasks = two_tasks();
+        let enums = output::unique_enums(&tasks);
+        let names: Vec<(&str, Vec<&str>)> = enums.iter().map(|(e, used_by)| (e.name.as_str(), used_by.clone())).collect();
+        assert_eq!(names, [("Command", vec!["AnotherTask", "NpmTask"]), ("CustomRegistry", vec!["AnotherTask", "NpmTask"])]);
+
+        let enum_file = output::enum_file(enums[0].0, &enums[0].1, &CodeTemplates::builtin()).unwrap();
+        assert!(enum_file.contains("// Used by: AnotherTask, NpmTask\n\n#nullable enable\n\nusing YamlDotNet.Serialization;\n\n"));
+        assert!(enum_file.contains("public enum Command {"));
+        assert!(!tasks[0].class_file(&CodeTemplates::builtin()).unwrap().contains("public enum"));
+    }
+
+    #[test]
+    fn description_is_kept_when_default_is_last() {
+        let parameter = parse_input_documentation("command", "'ci' | 'install'. Required. Command to run. Default: install.").unwrap();
+        assert_eq!(parameter.description, "Command to run");
+        assert_eq!(parameter.getter_default_arg.as_deref(), Some("Command.Install"));
+
+        let parameter = parse_input_documentation("workingDir", "string. Working folder that contains package.json.").unwrap();
+        assert_eq!(parameter.description, "Working folder that contains package.json");
+        assert_eq!(parameter.csharp_type, "string?");
+
+        let parameter = parse_input_documentation("verbose", "boolean. Optional. Use when command = ci. Verbose logging. Default: false.").unwrap();
+        assert_eq!(parameter.description, "Use when command = ci. Verbose logging");
+        assert_eq!(parameter.visible_when.as_deref(), Some("command = ci"));
+        assert_eq!(parameter.getter_default_arg.as_deref(), Some("false"));
+    }
+
+    #[test]
+    fn wrapped_descriptions_are_joined() {
+        let info = parse_yaml_lines("# Npm v1
+# Install and publish npm packages, or run an npm command.
+- task: Npm@1
+  inputs:
+    command: 'install' # 'ci' | 'install' | 'publish' | 'custom'. Required. The command to run,
+      # wrapped onto the next line. Default: install.
+  # Advanced
+    #verbose: # boolean. Verbose logging.
+").unwrap();
+        assert_eq!(info.parameters.len(), 2);
+        assert_eq!(info.parameters[0].description, "The command to run, wrapped onto the next line");
+        assert_eq!(info.parameters[0].getter_default_arg.as_deref(), Some("Command.Install"));
+        assert_eq!(info.parameters[1].description, "Verbose logging");
+    }
+
+    #[test]
+    fn allowed_values_become_enums() {
+        let parameter = parse_input_documentation("command", "string. Required. Allowed values: ci, install (Install packages), or publish. The command. Default: install.").unwrap();
+        assert_eq!(parameter.enum_options, Some(vec!["ci".to_string(), "install".to_string(), "publish".to_string()]));
+        assert_eq!(parameter.description, "The command");
+        assert_eq!(parameter.getter_default_arg.as_deref(), Some("Command.Install"));
+    }
+
+    #[test]
+    fn unquoted_pipe_lists_become_enums() {
+        let parameter = parse_input_documentation("command", "ci | install | publish. Required. The command.").unwrap();
+        assert_eq!(parameter.enum_options, Some(vec!["ci".to_string(), "install".to_string(), "publish".to_string()]));
+        assert_eq!(parameter.description, "The command");
+    }
+
+    #[test]
+    fn non_literal_defaults_are_quoted() {
+        assert_eq!(format_default_value("$(Build.ArtifactStagingDirectory)/*.nupkg", "string", false), "\"$(Build.ArtifactStagingDirectory)/*.nupkg\"");
+        assert_eq!(format_default_value("**/*.csproj", "string", false), "\"**/*.csproj\"");
+        assert_eq!(format_default_value("$(Agent.TempDirectory)\\*.log", "string", false), "@\"$(Agent.TempDirectory)\\*.log\"");
+        assert_eq!(format_default_value("say \"hi\"", "string", false), "\"say \\\"hi\\\"\"");
+
+        let parameter = parse_input_documentation("maxParallel", "int. Optional. Parallelism. Default: $(MaxParallel).").unwrap();
+        assert_eq!(parameter.csharp_type, "string");
+        assert_eq!(parameter.getter_default_arg.as_deref(), Some("\"$(MaxParallel)\""));
+    }
+
+    #[test]
+    fn periods_inside_clauses_survive() {
+        let parameter = parse_input_documentation("versionSpec", "string. Required when useGlobalJson = false. Version, e.g. 2.x or 3.1.100. See https://aka.ms/dotnet for details. Default: 1.0.0.").unwrap();
+        assert_eq!(parameter.required_when.as_deref(), Some("useGlobalJson = false"));
+        assert_eq!(parameter.description, "Version, e.g. 2.x or 3.1.100. See https://aka.ms/dotnet for details");
+        assert_eq!(parameter.getter_default_arg.as_deref(), Some("\"1.0.0\""));
+
+        let parameter = parse_input_documentation("sdkVersion", "string. Required when packageType = sdk && version = 3.1. SDK version.").unwrap();
+        assert_eq!(parameter.required_when.as_deref(), Some("packageType = sdk && version = 3.1"));
+        assert_eq!(parameter.description, "SDK version");
+    }
+
+    #[test]
+    fn descriptions_with_urls_and_abbreviations_are_kept_whole() {
+        let generated = generate("# Npm v1
+# Install and publish npm packages, or run an npm command.
+- task: Npm@1
+  inputs:
+    customCommand: # string. Required when command = custom. Command and arguments, e.g. 'ls -l'. See https://docs.npmjs.com/cli/v10/commands/npm-ls for details.
+    verbose: # boolean. Verbose logging, i.e. npm --verbose. Version 1.2.3 and later. Default: false.
+");
+        assert!(generated.class_code.contains("/// Command and arguments, e.g. 'ls -l'. See https://docs.npmjs.com/cli/v10/commands/npm-ls for details\n"));
+        assert!(generated.class_code.contains("/// Verbose logging, i.e. npm --verbose. Version 1.2.3 and later\n"));
+    }
+
+    #[test]
+    fn unicode_descriptions_are_normalized_and_escaped() {
+        let generated = generate("# Npm v1
+# Install and publish npm packages — or run an npm command.
+- task: Npm@1
+  inputs:
+    workingDir: # string. Dossier de travail\u{a0}contenant <package.json> & le\u{200b} cache — 作業フォルダー.
+");
+        assert!(generated.class_code.contains("/// Dossier de travail contenant &lt;package.json&gt; &amp; le cache — 作業フォルダー\n"));
+        assert!(generated.class_code.contains("Install and publish npm packages — or run an npm command."));
+    }
+
+    #[test]
+    fn input_names_with_dots_and_dashes_are_parsed() {
+        let info = parse_yaml_lines("# Npm v1
+# Install and publish npm packages, or run an npm command.
+- task: Npm@1
+  inputs:
+    feed.publish: # string. Publish feed.
+    #external-feed-credentials: # string. External feed credentials.
+").unwrap();
+        let names: Vec<(&str, &str)> = info.parameters.iter().map(|p| (p.yaml_name.as_str(), p.csharp_name.as_str())).collect();
+        assert_eq!(names, [("feed.publish", "FeedPublish"), ("external-feed-credentials", "ExternalFeedCredentials")]);
+    }
+
+    #[test]
+    fn snippet_structure_is_followed() {
+        let info = parse_yaml_lines("# Bash v3
+# Run a Bash script on macOS, Linux, or Windows.
+- task: Bash@3
+  inputs:
+      script: | # string. Required. Contents of the script.
+        echo \"# not a comment\"
+        # not documentation either
+      arguments: '--tag #1' # string. Arguments passed to the script.
+").unwrap();
+        let names: Vec<&str> = info.parameters.iter().map(|p| p.yaml_name.as_str()).collect();
+        assert_eq!(names, ["script", "arguments"]);
+        assert_eq!(info.parameters[0].description, "Contents of the script");
+        assert_eq!(info.parameters[1].description, "Arguments passed to the script");
+        assert!(info.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn crlf_and_two_space_snippets_are_parsed() {
+        let info = parse_yaml_lines("# Npm v1\r\n# Install and publish npm packages, or run an npm command.\r\ntask: Npm@1\r\ninputs:\r\n  command: 'install' # 'ci' | 'install'. Required. Command. Default: install.\r\n  #workingDir: # string. Working folder.\r\n").unwrap();
+        assert_eq!((info.task_name.as_str(), info.task_version.as_str()), ("Npm", "1"));
+        let names: Vec<&str> = info.parameters.iter().map(|p| p.yaml_name.as_str()).collect();
+        assert_eq!(names, ["command", "workingDir"]);
+        assert_eq!(info.parameters[1].description, "Working folder");
+        assert!(info.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn identifiers_are_valid_csharp() {
+        assert_eq!(csharp_identifier("params"), "Params");
+        assert_eq!(csharp_identifier("7zipPath"), "_7zipPath");
+
+        let generated = generate("# Npm v1
+# Install and publish npm packages, or run an npm command.
+- task: Npm@1
+  inputs:
+    outputs: # string. Output folder.
+    npmTask: # string. Npm task.
+");
+        assert!(generated.class_code.contains("public string? OutputsInput {"));
+        assert!(generated.class_code.contains("public string? NpmTaskInput {"));
+    }
+
+    #[test]
+    fn repeated_inputs_are_deduplicated() {
+        let info = parse_yaml_lines("# Npm v1
+# Install and publish npm packages, or run an npm command.
+- task: Npm@1
+  inputs:
+    #command: # string. Command.
+    workingDir: # string. Working folder.
+    command: 'install' # 'ci' | 'install'. Required. The npm command to run. Default: install.
+").unwrap();
+        let names: Vec<&str> = info.parameters.iter().map(|p| p.yaml_name.as_str()).collect();
+        assert_eq!(names, ["command", "workingDir"]);
+        assert_eq!(info.parameters[0].description, "The npm command to run");
+    }
+
+    #[test]
+    fn unparsed_lines_are_recorded() {
+        let info = parse_yaml_lines("# Npm v1
+# Install and publish npm packages, or run an npm command.
+- task: Npm@1
+  inputs:
+    command: 'install' # 'ci' | 'install'. Required. The npm command to run. Default: install.
+    workingDir: # See the working folder docs
+    verbose: true
+").unwrap();
+        assert_eq!(info.parameters.len(), 1);
+        let lines: Vec<usize> = info.diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, [6, 7]);
+        assert!(info.diagnostics.iter().all(Diagnostic::is_error));
+        assert_eq!(info.diagnostics[0].column_range(), (19, 45));
+        assert_eq!(info.diagnostics[1].column_range(), (5, 17));
+    }
+
+    #[test]
+    fn diagnostics_point_at_the_offending_columns() {
+        let diagnostic = Diagnostic::new(Severity::Error, "input documentation not recognized; the input is skipped", 5, "    workingDir: # See the docs", 18..30);
+        assert_eq!(
+            diagnostics::render("npm-v1", &[diagnostic]),
+            "error: input documentation not recognized; the input is skipped\n --> npm-v1:6:19\n  |\n6 |     workingDir: # See the docs\n  |                   ^^^^^^^^^^^^\n"
+        );
+    }
+
+    #[test]
+    fn step_keys_are_skipped() {
+        let info = parse_yaml_lines("# Npm v1
+# Install and publish npm packages, or run an npm command.
+- task: Npm@1
+  displayName: 'Install packages' # Shown in the pipeline run.
+  condition: succeeded()
+  inputs:
+    command: 'install' # 'ci' | 'install'. Required. The npm command to run. Default: install.
+  continueOnError: true
+  env:
+    NPM_TOKEN: $(token) # string. The token.
+").unwrap();
+        let names: Vec<&str> = info.parameters.iter().map(|p| p.yaml_name.as_str()).collect();
+        assert_eq!(names, ["command"]);
+        assert!(info.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn tasks_without_inputs_are_generated() {
+        let generated = generate("# Manual validation v1
+# Pause a pipeline run to wait for manual interaction.
+- task: ManualValidation@1
+");
+        assert!(generated.class_code.contains("public ManualValidationTask() : base(\"ManualValidation@1\")\n    {\n    }\n}\n"));
+        assert!(generated.enums.is_empty());
+    }
+
+    #[test]
+    fn remarks_section_is_parsed() {
+        let remarks = page_sections::parse_remarks(r#"<html><div class="content">
+<h2 id="remarks">Remarks</h2>
+<p>Use <code>npm ci</code> in CI, see <a href="https://docs.npmjs.com/cli">the npm docs</a>.</p>
+<ul><li><p>Caches &amp; more.</p></li></ul>
+<h2 id="examples">Examples</h2>
+<p>Not a remark.</p>
+</div></html>"#);
+        assert_eq!(remarks, ["Use `npm ci` in CI, see [the npm docs](https://docs.npmjs.com/cli).", "Caches & more."]);
+        assert_eq!(
+            task_json::help_markdown_to_xml(&remarks[0]),
+            r#"Use <c>npm ci</c> in CI, see <see href="https://docs.npmjs.com/cli">the npm docs</see>."#
+        );
+    }
+
+    #[test]
+    fn examples_become_example_blocks() {
+        let examples = page_sections::parse_examples(r#"<html><div class="content">
+<h2 id="examples">Examples</h2>
+<pre><code class="lang-yaml">- task: Npm@1
+  inputs:
+    command: 'ci'
+</code></pre>
+<pre><code class="lang-powershell">npm ci &amp;&amp; npm test</code></pre>
+<h2 id="requirements">Requirements</h2>
+<pre><code class="lang-yaml">- not: an example</code></pre>
+</div></html>"#);
+        assert_eq!(examples, ["- task: Npm@1\n  inputs:\n    command: 'ci'"]);
+
+        let mut task = task_from(NPM_SNIPPET);
+        task.examples = vec!["- task: Npm@1\n  condition: and(succeeded(), ne(variables.skip, 'true'))".to_string()];
+        let generated = generate_csharp(&task, "AzureDevOpsTask", &AccessorSurface::sharpliner_default(), &CodeTemplates::builtin()).unwrap();
+        assert!(generated.class_code.contains(
+            "/// <example>\n/// <code lang=\"yaml\">\n/// - task: Npm@1\n///   condition: and(succeeded(), ne(variables.skip, 'true'))\n/// </code>\n/// </example>\n"
+        ));
+    }
+
+    #[test]
+    fn missing_task_version_comes_from_the_page() {
+        let mut info = parse_yaml_lines("# Npm v1
+# Install and publish npm packages, or run an npm command.
+- task: Npm
+  inputs:
+    workingDir: # string. Working folder that contains package.json.
+").unwrap();
+        assert_eq!(info.task_name, "Npm");
+        assert_eq!(info.task_version, "");
+
+        fill_task_reference_from_page(&mut info, "<html><h1>Npm@1 - npm v1 task</h1></html>", "https://example.invalid/npm?view=azure-pipelines");
+        assert_eq!(info.task_version, "1");
+
+        let mut info = parse_yaml_lines("# Npm v1\n# Install and publish npm packages.\n").unwrap();
+        fill_task_reference_from_page(&mut info, "<html><h1>Npm@1 - npm v1 task</h1></html>", "https://example.invalid/npm-v2?view=azure-pipelines");
+        assert_eq!((info.task_name.as_str(), info.task_version.as_str()), ("Npm", "2"));
+    }
+
+    #[test]
+    fn enum_member_names_are_sanitized() {
+        assert_eq!(enum_member_name("4.x"), "_4_x");
+        assert_eq!(enum_member_name("3.1"), "_3_1");
+        assert_eq!(enum_member_name("ubuntu-latest"), "UbuntuLatest");
+        assert_eq!(format_default_value("'3.1'", "DotnetVersion", true), "DotnetVersion._3_1");
+    }
+
+    #[test]
+    fn required_phrasing_variants_are_recognized() {
+        for documentation in ["boolean. (Required) Verbose logging.", "boolean. This input is required. Verbose logging.", "boolean. Mandatory: Verbose logging."] {
+            let parameter = parse_input_documentation("verbose", documentation).unwrap();
+            assert_eq!(parameter.csharp_type, "bool", "{}", documentation);
+            assert_eq!(parameter.description, "Verbose logging", "{}", documentation);
+        }
+
+        let parameter = parse_input_documentation("verbose", "boolean. (Optional) Verbose logging.").unwrap();
+        assert_eq!(parameter.csharp_type, "bool?");
+        assert_eq!(parameter.description, "Verbose logging");
+
+        let parameter = parse_input_documentation("feed", "string. Required if command = push. Feed to publish to.").unwrap();
+        assert_eq!(parameter.required_when.as_deref(), Some("command = push"));
+    }
+
+    #[test]
+    fn union_types_become_strings() {
+        let parameter = parse_input_documentation("failOnStderr", "string or boolean. Optional. Fail on standard error output.").unwrap();
+        assert_eq!(parameter.csharp_type, "string?");
+        assert_eq!(parameter.union_types, ["string", "boolean"]);
+        assert_eq!(parameter.description, "Fail on standard error output");
+
+        let parameter = parse_input_documentation("failOnStderr", "string | boolean. Optional. Fail on standard error output.").unwrap();
+        assert_eq!(parameter.enum_options, None);
+    }
+
+    #[test]
+    fn boolean_defaults_are_normalized() {
+        for value in ["True", "'false'", "FALSE", "\"true\""] {
+            assert_eq!(format_default_value(value, "bool", false), value.trim_matches(|c| c == '\'' || c == '"').to_lowercase());
+        }
+    }
+
+    #[test]
+    fn task_group_steps_become_nested_steps() {
+        let group = task_group::parse_task_group(r#"{
+            "name": "Build and test",
+            "version": { "major": 2 },
+            "inputs": [{ "name": "configuration", "type": "string", "label": "Configuration", "required": true }],
+            "tasks": [
+                {
+                    "displayName": "dotnet build",
+                    "enabled": true,
+                    "task": { "id": "5541a522-603c-47ad-91fc-a4b1d163081b", "versionSpec": "2.*" },
+                    "inputs": { "command": "build", "arguments": "-c $(configuration)", "projects": "" }
+                },
+                {
+                    "displayName": "Disabled",
+                    "enabled": false,
+                    "task": { "id": "d9bafed4-0b18-4f58-968d-86655b4d2ce9", "versionSpec": "2.*" }
+                }
+            ]
+        }"#).unwrap();
+        assert_eq!(group.info.task_name, "BuildAndTest");
+        assert_eq!(group.steps.len(), 1);
+
+        let mut task = task_from(NPM_SNIPPET);
+        task.class_name = "BuildAndTestTask".to_string();
+        task.info = group.info;
+        task.steps = group.steps;
+        let generated = generate_csharp(&task, "AzureDevOpsTask", &AccessorSurface::sharpliner_default(), &CodeTemplates::builtin()).unwrap();
+        assert!(generated.class_code.contains(": base(\"BuildAndTest@2\")"));
+        assert!(generated.class_code.contains(
+            "    public record class Step1 : AzureDevOpsTask {\n        public Step1(Func<string, string> expand) : base(\"5541a522-603c-47ad-91fc-a4b1d163081b@2\")\n        {\n            SetProperty(\"command\", expand(\"build\"));\n            SetProperty(\"arguments\", expand(\"-c $(configuration)\"));\n        }\n    }\n"
+        ));
+        assert!(generated.class_code.contains("        new Step1(Expand),\n    };\n"));
+        assert!(generated.class_code.contains(
+            "    private string Expand(string value) => value\n        .Replace(\"$(configuration)\", GetString(\"configuration\") ?? \"$(configuration)\");\n"
+        ));
+        assert!(!generated.class_code.contains("Step2"));
+    }
+
+    #[test]
+    fn actions_are_generated_like_tasks() {
+        const ACTION_YML: &str = "name: 'Build docs'
+description: |
+  Builds the documentation
+  and publishes it.
+inputs:
+  # Where the output goes
+  output:
+    description: 'Output folder'
+    required: true
+  clean:
+    description: >
+      Whether to delete the output
+      folder first.
+    default: 'false'
+runs:
+  using: 'node20'
+  main: 'index.js'
+";
+        let action = github_action::parse_action_yml(ACTION_YML, "https://github.com/contoso/build-docs/blob/v1/action.yml").unwrap();
+        let task = action_task(action, ACTION_YML);
+        assert_eq!(task.class_name, "BuildDocsAction");
+        assert_eq!((task.info.task_name.as_str(), task.info.task_version.as_str()), ("contoso/build-docs", "1"));
+        assert_eq!(task.info.parameters.iter().map(|p| (p.yaml_name.as_str(), p.base_csharp_type.as_str())).collect::<Vec<_>>(), [("output", "string"), ("clean", "bool")]);
+        assert_eq!(task.info.parameters[1].description, "Whether to delete the output folder first.");
+
+        let generated = generate_csharp(&task, "GitHubAction", &AccessorSurface::sharpliner_default(), &CodeTemplates::builtin()).unwrap();
+        assert!(generated.class_code.contains("public record class BuildDocsAction : GitHubAction {"));
+        assert!(generated.class_code.contains(": base(\"contoso/build-docs@v1\")"));
+        assert!(generated.class_code.contains("/// Generated C# model for the GitHub Action: Build docs (contoso/build-docs@v1).\n"));
+        assert!(generated.class_code.contains("public string? Output {"));
+        assert!(generated.class_code.contains("public bool Clean {"));
+        assert!(!generated.usings.contains(&"Sharpliner.AzureDevOps.Tasks".to_string()));
+        assert!(generated.class_file(&CodeTemplates::builtin()).unwrap().contains("// Source Task: contoso/build-docs v1\n"));
+
+        // Local actions have no version to show
+        let local = action_task(github_action::parse_action_yml(ACTION_YML, "actions/build-docs/action.yml").unwrap(), ACTION_YML);
+        let generated = generate_csharp(&local, "GitHubAction", &AccessorSurface::sharpliner_default(), &CodeTemplates::builtin()).unwrap();
+        assert!(generated.class_code.contains(": base(\"./actions/build-docs\")"));
+        assert!(generated.class_file(&CodeTemplates::builtin()).unwrap().contains("// Source Task: ./actions/build-docs\n"));
+    }
+
+    #[test]
+    fn repo_folders_match_the_exact_version() {
+        let repo = temp_dir("tasks-repo");
+        for folder in ["XV2", "XV12"] {
+            std::fs::create_dir_all(repo.join("Tasks").join(folder)).unwrap();
+            std::fs::write(repo.join("Tasks").join(folder).join("task.json"), "{}").unwrap();
+        }
+        std::fs::create_dir_all(repo.join("Tasks").join("Common")).unwrap();
+        let repo_path = repo.display().to_string();
+        let folders = |version| task_json::repo_task_jsons(&repo_path, version).unwrap().iter()
+            .map(|location| std::path::Path::new(location).parent().unwrap().file_name().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        assert_eq!(folders(None), ["XV12", "XV2"]);
+        assert_eq!(folders(Some(2)), ["XV2"]);
+        assert_eq!(folders(Some(12)), ["XV12"]);
+        std::fs::remove_dir_all(&repo).unwrap();
+    }
+
+    #[test]
+    fn conflicting_enums_are_renamed_after_their_class() {
+        let npm = task_from(NPM_SNIPPET);
+        let mut same = task_from(NPM_SNIPPET);
+        same.class_name = "SameTask".to_string();
+        let mut other = task_from(&NPM_SNIPPET.replace("'publish' | 'custom'", "'publish' | 'exec'"));
+        other.class_name = "OtherTask".to_string();
+        let mut tasks = vec![npm, other, same];
+        tasks.sort_by(|a, b| a.class_name.cmp(&b.class_name));
+        disambiguate_enum_names(&mut tasks);
+
+        let command_type = |task: &TaskDefinition| task.info.parameters.iter()
+            .find(|p| p.yaml_name == "command").unwrap().base_csharp_type.clone();
+        assert_eq!(tasks.iter().map(|t| (t.class_name.as_str(), command_type(t))).collect::<Vec<_>>(), [
+            ("NpmTask", "Command".to_string()),
+            ("OtherTask", "OtherCommand".to_string()),
+            ("SameTask", "Command".to_string()),
+        ]);
+        let generated = tasks.iter()
+            .map(|task| generate_csharp(task, "AzureDevOpsTask", &AccessorSurface::sharpliner_default(), &CodeTemplates::builtin()).unwrap())
+            .collect::<Vec<_>>();
+        assert!(generated[1].class_code.contains("public OtherCommand Command"));
+        let enums = output::unique_enums(&generated);
+        let used_by = |name: &str| enums.iter().find(|(e, _)| e.name == name).map(|(_, used_by)| used_by.clone());
+        assert_eq!(used_by("Command"), Some(vec!["NpmTask", "SameTask"]));
+        assert_eq!(used_by("OtherCommand"), Some(vec!["OtherTask"]));
+    }
+
+    #[test]
+    fn archive_entries_keep_relative_paths() {
+        assert_eq!(archive::entry_name(std::path::Path::new("Enums/Command.cs")), "Enums/Command.cs");
+        assert_eq!(archive::entry_name(std::path::Path::new("./out/../Generated/NpmTask.cs")), "Generated/NpmTask.cs");
+        assert_eq!(archive::entry_name(&std::env::temp_dir().join("NpmTask.cs")), "NpmTask.cs");
+    }
+
+    #[test]
+    fn lockfile_hashes_sources_and_files() {
+        let task = task_from(NPM_SNIPPET);
+        let lockfile: serde_json::Value = serde_json::from_str(
+            &archive::lockfile(&[task], &[("NpmTask.cs".to_string(), "class".to_string())]).unwrap()
+        ).unwrap();
+        assert_eq!(lockfile["tasks"][0]["taskName"].as_str(), Some("Npm"));
+        assert_eq!(lockfile["tasks"][0]["sourceHash"].as_str(), Some(cache::hash(NPM_SNIPPET).as_str()));
+        assert_eq!(lockfile["files"][0]["name"].as_str(), Some("NpmTask.cs"));
+        assert_eq!(lockfile["files"][0]["hash"].as_str(), Some(cache::hash("class").as_str()));
+    }
+
+    #[test]
+    fn invalid_usage_exits_with_2() {
+        let args = |extra: &[&str]| Args::parse_from([env!("CARGO_PKG_NAME"), "--url", "https://example.invalid"].iter().chain(extra).copied());
+        assert!(check_usage(&args(&[])).is_ok());
+        for extra in [&["--catalog", "Tasks"][..], &["--namespace", "My..Tasks"], &["--check"], &["--header", "no colon"], &["--archive", "Tasks.rar"]] {
+            let failure = check_usage(&args(extra)).unwrap_err();
+            assert_eq!(errors::exit_code(&failure), 2, "{}", failure);
+        }
+
+        let unreadable = read_path_or_url("no/such/action.yml").unwrap_err();
+        assert_eq!(errors::exit_code(unreadable.as_ref()), 2);
+    }
+
+    #[test]
+    fn yaml_files_are_parsed_without_fetching() {
+        let dir = temp_dir("yaml-file");
+        let path = dir.join("npm.yml");
+        std::fs::write(&path, NPM_SNIPPET).unwrap();
+        let location = path.to_str().unwrap().to_string();
+
+        let task = load_task(&TaskSource::YamlFile(location.clone())).unwrap();
+        assert_eq!((task.info.task_name.as_str(), task.info.task_version.as_str(), task.class_name.as_str()), ("Npm", "1", "NpmTask"));
+        let inputs: Vec<&str> = task.info.parameters.iter().map(|p| p.yaml_name.as_str()).collect();
+        assert_eq!(inputs, ["command", "workingDir", "verbose", "customRegistry"]);
+        assert!(task.deprecation.is_none());
+
+        std::fs::remove_file(&path).unwrap();
+        let Err(missing) = load_task(&TaskSource::YamlFile(location)) else { panic!("a missing file was loaded") };
+        assert_eq!(errors::exit_code(missing.as_ref()), 2);
+    }
+
+    #[test]
+    fn sources_are_downloaded_from_their_resolved_urls() {
+        let task_json = TaskSource::TaskJson("https://github.com/microsoft/azure-pipelines-tasks/blob/master/Tasks/NpmV1/task.json".to_string());
+        assert_eq!(task_json.url().as_deref(), Some("https://raw.githubusercontent.com/microsoft/azure-pipelines-tasks/master/Tasks/NpmV1/task.json"));
+        assert_eq!(TaskSource::Action("https://ghe.contoso.invalid/build/action.yml".to_string()).url().as_deref(), Some("https://ghe.contoso.invalid/build/action.yml"));
+        assert_eq!(TaskSource::TaskJson("Tasks/NpmV1/task.json".to_string()).url(), None);
+        assert_eq!(TaskSource::YamlFile("-".to_string()).url(), None);
+
+        // --header values follow the inputs to their hosts, not just those of --url
+        http::allow_header_hosts(task_json.url());
+        assert!(http::is_header_host("https://raw.githubusercontent.com/microsoft/azure-pipelines-tasks/master/Tasks/NpmV2/task.json"));
+    }
+
+    #[test]
+    fn deprecated_tasks_point_at_their_generated_replacement() {
+        let mut deprecated = task_from(NPM_SNIPPET);
+        deprecated.deprecation = Some(deprecation::from_message("This task is deprecated; use Npm@2 instead."));
+        let mut replacement = task_from(&NPM_SNIPPET.replace("Npm@1", "Npm@2").replace("Npm v1", "Npm v2"));
+        replacement.class_name = "NpmV2Task".to_string();
+
+        let mut tasks = [deprecated, replacement];
+        link_replacement_classes(&mut tasks);
+        assert_eq!(tasks[0].deprecation.as_ref().and_then(|d| d.replacement_class.as_deref()), Some("NpmV2Task"));
+        assert!(tasks[1].deprecation.is_none());
+
+        let code = generate_csharp(&tasks[0], "AzureDevOpsTask", &AccessorSurface::sharpliner_default(), &CodeTemplates::builtin()).unwrap().class_code;
+        assert!(code.contains("[Obsolete(\"Npm@1 is deprecated. Use NpmV2Task instead.\")]\npublic record class NpmTask"), "{}", code);
+        assert!(code.contains("Npm@1 is deprecated; use <see cref=\"NpmV2Task\"/> instead."));
+    }
+
+    #[test]
+    fn manifest_names_the_generated_properties() {
+        let mut task = task_from("# Npm v1
+# Install and publish npm packages, or run an npm command.
+- task: Npm@1
+  inputs:
+    outputs: # string. Output folder.
+");
+        task.info.parameters = member_parameters(&task);
+        let manifest: serde_json::Value = serde_json::from_str(&manifest::render_manifest(&[task], |_| "AzureDevOpsTask").unwrap()).unwrap();
+        assert_eq!(manifest["tasks"][0]["inputs"][0]["propertyName"].as_str(), Some("OutputsInput"));
+    }
+
+    #[test]
+    fn merged_inputs_keep_the_snippet_example() {
+        let snippet = parse_yaml_lines("# Npm v1
+# Install and publish npm packages, or run an npm command.
+- task: Npm@1
+  inputs:
+    workingDir: 'src/app' # string. Working folder that contains package.json.
+").unwrap().parameters;
+        assert_eq!(snippet[0].example.as_deref(), Some("src/app"));
+
+        let table = [inputs_table::DocumentedInput {
+            yaml_name: "workingDir".to_string(),
+            alias: None,
+            label: "Working folder".to_string(),
+            input_type: "string".to_string(),
+            required_status: "Optional".to_string(),
+            allowed_values: Vec::new(),
+            default_value: None,
+            description: "The folder containing package.json.".to_string(),
+        }];
+        let merged = inputs_table::merge_parameters(snippet, &table, "https://example.invalid/npm-v1");
+        assert_eq!(merged[0].description, "The folder containing package.json.");
+        assert_eq!(merged[0].example.as_deref(), Some("src/app"));
+        assert!(property_context(&merged[0], &AccessorSurface::sharpliner_default()).docs.contains("For example <c>src/app</c>."));
+    }
+
+    #[test]
+    fn cache_entries_are_named_by_a_stable_hash() {
+        assert_eq!(cache::hash(""), "cbf29ce484222325");
+        assert_eq!(cache::hash("a"), "af63dc4c8601ec8c");
+        let dir = std::path::Path::new("cache");
+        let path = cache::entry_path(dir, "https://example.invalid/npm-v1");
+        assert_eq!(path, cache::entry_path(dir, "https://example.invalid/npm-v1"));
+        assert_ne!(path, cache::entry_path(dir, "https://example.invalid/npm-v2"));
+        assert_eq!(path, dir.join(format!("{}.html", cache::hash("https://example.invalid/npm-v1"))));
+    }
+
+    #[test]
+    fn cached_pages_expire_and_are_refreshed_by_touch() {
+        let dir = temp_dir("cache-ttl");
+        let path = cache::entry_path(&dir, "https://example.invalid/npm-v1");
+        let validators = cache::Validators { etag: Some("\"abc\"".to_string()), last_modified: None };
+        cache::write(&path, "page", &validators).unwrap();
+        let ttl = std::time::Duration::from_secs(60);
+        assert_eq!(cache::read_fresh(&path, ttl).as_deref(), Some("page"));
+
+        let an_hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        std::fs::File::options().append(true).open(&path).unwrap().set_modified(an_hour_ago).unwrap();
+        assert_eq!(cache::read_fresh(&path, ttl), None);
+        // Stale copies are still there to revalidate, with their validators
+        let cached = cache::read(&path).unwrap();
+        assert_eq!(cached.body, "page");
+        assert_eq!(cached.validators.etag.as_deref(), Some("\"abc\""));
+
+        cache::touch(&path).unwrap();
+        assert_eq!(cache::read_fresh(&path, ttl).as_deref(), Some("page"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cached_pages_without_headers_have_no_validators() {
+        let dir = temp_dir("cache-headers");
+        let path = cache::entry_path(&dir, "https://example.invalid/npm-v1");
+        std::fs::write(&path, "page").unwrap();
+        let cached = cache::read(&path).unwrap();
+        assert_eq!(cached.body, "page");
+        assert!(cached.validators.etag.is_none() && cached.validators.last_modified.is_none());
+        assert!(cache::read(&dir.join("missing.html")).is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn not_modified_serves_and_refreshes_the_cached_copy() {
+        let dir = temp_dir("cache-revalidation");
+        let url = "https://example.invalid/npm-v1";
+        let path = cache::entry_path(&dir, url);
+        cache::write(&path, "page", &cache::Validators::default()).unwrap();
+        let an_hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        std::fs::File::options().append(true).open(&path).unwrap().set_modified(an_hour_ago).unwrap();
+
+        assert_eq!(http::not_modified_copy(url, cache::read(&path), Some(&path)).unwrap(), "page");
+        assert_eq!(cache::read_fresh(&path, std::time::Duration::from_secs(60)).as_deref(), Some("page"));
+        assert_eq!(http::not_modified_copy(url, None, Some(&path)).unwrap_err().kind, FailureKind::Network);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn offline_runs_only_use_the_cache() {
+        let dir = temp_dir("cache-offline");
+        let url = "https://example.invalid/npm-v1";
+        let path = cache::entry_path(&dir, url);
+        cache::write(&path, "page", &cache::Validators::default()).unwrap();
+
+        assert_eq!(http::offline_copy(url, cache::read(&path), Some(&path)).unwrap(), "page");
+        assert_eq!(http::offline_copy(url, None, Some(&path)).unwrap_err().kind, FailureKind::Network);
+        assert_eq!(http::offline_copy(url, None, None).unwrap_err().kind, FailureKind::Network);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn recorded_pages_are_replayed() {
+        let fixtures = temp_dir("fixtures");
+        let url = "https://example.invalid/npm-v1";
+        http::record(&fixtures, url, "page").unwrap();
+
+        assert_eq!(http::replay(&fixtures, url).unwrap(), "page");
+        assert_eq!(std::fs::read_to_string(cache::entry_path(&fixtures, url).with_extension("url")).unwrap(), format!("{}\n", url));
+        assert_eq!(http::replay(&fixtures, "https://example.invalid/npm-v2").unwrap_err().kind, FailureKind::Network);
+        std::fs::remove_dir_all(&fixtures).unwrap();
+    }
+}
@@ -0,0 +1,201 @@
+// --- Source Generator Manifest ---
+// A JSON description of the parsed tasks, shaped so a C# source generator can produce the
+// task classes at compile time instead of committing the generated .cs files. It mirrors what
+// the generated classes carry: the inputs with their types and documentation, the task's
+// constants, its output variables and its deprecation.
+
+use serde::Serialize;
+
+use crate::{InputKind, OutputVariable, ProcessedParameter, TaskDefinition};
+
+// Bumped whenever the shape of the manifest changes incompatibly
+const MANIFEST_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest<'a> {
+    schema_version: u32,
+    generator: String,
+    tasks: Vec<ManifestTask<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestTask<'a> {
+    task_name: &'a str,
+    task_version: &'a str,
+    class_name: &'a str,
+    base_class: &'a str,
+    summary: &'a str,
+    documentation_url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    task_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_display_name: Option<&'a str>, // e.g. "dotnet $(command)"
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    demands: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum_agent_version: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deprecation: Option<ManifestDeprecation<'a>>,
+    inputs: Vec<ManifestInput<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    outputs: Vec<ManifestOutput<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestDeprecation<'a> {
+    message: String, // As in the [Obsolete] attribute
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replacement: Option<String>, // e.g. 'AzureFunctionApp@2'
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replacement_class: Option<&'a str>, // When the replacement was generated in the same run
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestOutput<'a> {
+    name: &'a str,
+    description: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestInput<'a> {
+    name: &'a str,          // Name of the input in YAML
+    property_name: &'a str, // Name of the generated C# property
+    kind: &'static str,     // string | bool | int | enum
+    csharp_type: &'a str,   // Type without nullability, e.g. 'bool' or the enum name
+    nullable: bool,
+    required: bool, // Always; see requiredWhen for inputs required under a condition
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refers_to: Option<&'static str>, // What a string input names: filePath | secureFile | connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connection_types: Option<&'a str>, // The kinds of service connection, e.g. 'AzureRM'
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default: Option<&'a str>, // Already formatted as a C# expression
+    description: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    labels: &'a [String], // Display labels of the options, in the same order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<&'a str>, // The section the input is listed under, e.g. 'Advanced'
+    #[serde(skip_serializing_if = "Option::is_none")]
+    visible_when: Option<&'a str>, // e.g. "command = install"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    required_when: Option<&'a str>, // e.g. "command = push"
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    aliases: &'a [String], // Other YAML names of the input
+    #[serde(skip_serializing_if = "Option::is_none")]
+    example: Option<&'a str>, // The value the documentation's snippet shows
+}
+
+pub fn render_manifest(tasks: &[TaskDefinition], base_class: impl Fn(&TaskDefinition) -> &str) -> Result<String, serde_json::Error> {
+    let manifest = Manifest {
+        schema_version: MANIFEST_SCHEMA_VERSION,
+        generator: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        tasks: tasks.iter().map(|t| ManifestTask {
+            task_name: &t.info.task_name,
+            task_version: &t.info.task_version,
+            class_name: &t.class_name,
+            base_class: base_class(t),
+            summary: &t.info.task_summary,
+            documentation_url: &t.documentation_url,
+            task_id: t.task_id.as_deref(),
+            default_display_name: t.instance_name_format.as_deref(),
+            demands: &t.requirements.demands,
+            minimum_agent_version: t.requirements.minimum_agent_version.as_deref(),
+            deprecation: t.deprecation.as_ref().map(|d| ManifestDeprecation {
+                message: d.obsolete_message(&t.info.task_name, &t.info.task_version),
+                replacement: d.replacement.as_ref().map(|r| format!("{}@{}", r.task_name, r.task_version)),
+                replacement_class: d.replacement_class.as_deref(),
+            }),
+            inputs: t.info.parameters.iter().map(manifest_input).collect(),
+            outputs: t.output_variables.iter().map(manifest_output).collect(),
+        }).collect(),
+    };
+
+    serde_json::to_string_pretty(&manifest)
+}
+
+fn manifest_input(p: &ProcessedParameter) -> ManifestInput<'_> {
+    let kind = match p.base_csharp_type.as_str() {
+        "bool" => "bool",
+        "int" => "int",
+        _ if p.enum_options.is_some() => "enum",
+        _ => "string",
+    };
+
+    ManifestInput {
+        name: &p.yaml_name,
+        property_name: &p.csharp_name,
+        kind,
+        csharp_type: &p.base_csharp_type,
+        nullable: p.is_nullable,
+        required: p.is_required,
+        refers_to: Some(p.input_kind.name()).filter(|_| p.input_kind != InputKind::Plain),
+        connection_types: match &p.input_kind {
+            InputKind::ServiceConnection(kinds) if !kinds.is_empty() => Some(kinds),
+            _ => None,
+        },
+        default: p.getter_default_arg.as_deref(),
+        description: &p.description,
+        options: p.enum_options.as_deref(),
+        labels: &p.enum_labels,
+        group: p.group.as_deref(),
+        visible_when: p.visible_when.as_deref(),
+        required_when: p.required_when.as_deref(),
+        aliases: &p.aliases,
+        example: p.example.as_deref(),
+    }
+}
+
+fn manifest_output(v: &OutputVariable) -> ManifestOutput<'_> {
+    ManifestOutput { name: &v.name, description: &v.description }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deprecation::{Deprecation, TaskReference};
+
+    #[test]
+    fn manifest_carries_the_class_metadata() {
+        let mut task = crate::tests::task_from("# Azure CLI v1
+# Run Azure CLI commands.
+- task: AzureCLI@1
+  inputs:
+    connectedServiceNameARM: # string. Alias: azureSubscription. Required. Azure subscription.
+    scriptLocation: 'scriptPath' # 'inlineScript' | 'scriptPath'. Required. Script Location. Default: scriptPath.
+    scriptPath: # string. Optional. Use when scriptLocation = scriptPath. Script Path.
+  # Advanced
+    cwd: # string. Working Directory.
+");
+        task.info.parameters[1].enum_labels = vec!["Inline script".to_string(), "Script path".to_string()];
+        task.info.parameters[2].input_kind = InputKind::FilePath;
+        task.output_variables.push(OutputVariable { name: "exitCode".to_string(), description: "The script's exit code.".to_string() });
+        task.requirements.demands.push("azureps".to_string());
+        task.deprecation = Some(Deprecation {
+            replacement: Some(TaskReference { task_name: "AzureCLI".to_string(), task_version: "2".to_string() }),
+            replacement_class: None,
+        });
+
+        let manifest: serde_json::Value = serde_json::from_str(&render_manifest(&[task], |_| "AzureDevOpsTask").unwrap()).unwrap();
+        let task = &manifest["tasks"][0];
+        assert_eq!(task["demands"], serde_json::json!(["azureps"]));
+        assert_eq!(task["outputs"], serde_json::json!([{ "name": "exitCode", "description": "The script's exit code." }]));
+        assert_eq!(task["deprecation"], serde_json::json!({
+            "message": "AzureCLI@1 is deprecated. Use AzureCLI@2 instead.",
+            "replacement": "AzureCLI@2",
+        }));
+
+        let [connection, location, path, cwd] = &task["inputs"].as_array().unwrap()[..] else { panic!("expected four inputs") };
+        assert_eq!((connection["required"].as_bool(), connection["refersTo"].as_str()), (Some(true), Some("connection")));
+        assert_eq!(location["labels"], serde_json::json!(["Inline script", "Script path"]));
+        assert_eq!((path["required"].as_bool(), path["refersTo"].as_str()), (Some(false), Some("filePath")));
+        assert_eq!(cwd["group"].as_str(), Some("Advanced"));
+        assert!(location.get("refersTo").is_none() && location.get("group").is_none());
+    }
+}
@@ -0,0 +1,346 @@
+// --- Output Assembly ---
+// Generated code is kept in pieces (usings, enums, class) so it can either be written as
+// one file per class, or merged with other tasks into a single file.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::errors::{Failure, FailureKind};
+use crate::templates::{CodeTemplates, FileContext, SourceContext};
+
+#[derive(Debug, Clone)]
+pub struct GeneratedEnum {
+    pub name: String,
+    pub code: String,
+    pub usings: Vec<String>, // What the enum needs on its own, when written to its own file
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedTask {
+    pub class_name: String,
+    pub task_name: String,
+    pub task_version: String,
+    pub documentation_url: String,
+    pub summary: String, // One line, XML-escaped
+    pub usings: Vec<String>,
+    pub enums: Vec<GeneratedEnum>,
+    pub class_code: String,
+}
+
+impl GeneratedTask {
+    // 'Name vVersion', or just the name for sources without a version, like local actions
+    pub fn source_name(&self) -> String {
+        match self.task_version.as_str() {
+            "" => self.task_name.clone(),
+            version => format!("{} v{}", self.task_name, version),
+        }
+    }
+
+    fn source(&self) -> SourceContext<'_> {
+        SourceContext {
+            name: self.source_name(),
+            task_name: &self.task_name,
+            task_version: &self.task_version,
+            class_name: &self.class_name,
+            documentation_url: &self.documentation_url,
+        }
+    }
+
+    // Renders the task's class alone, for when its enums are written to their own files
+    pub fn class_file(&self, templates: &CodeTemplates) -> Result<String, Failure> {
+        render_file(templates, "task", vec![self.source()], Vec::new(), &self.usings, self.class_code.clone())
+    }
+
+    // Renders the task as a standalone C# file
+    pub fn to_file(&self, templates: &CodeTemplates) -> Result<String, Failure> {
+        let enums_code = self.enums.iter()
+            .map(|e| e.code.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let body = format!(
+r#"// --- Enums ---
+
+{enums_code}
+{class_code}"#,
+            enums_code = enums_code,
+            class_code = self.class_code,
+        );
+        render_file(templates, "task", vec![self.source()], Vec::new(), &self.usings, body)
+    }
+}
+
+// Renders all tasks into one file. Usings and identical enums are de-duplicated, and
+// everything is ordered by name so the merged file is stable regardless of input order.
+pub fn merge_tasks(tasks: &[GeneratedTask], templates: &CodeTemplates) -> Result<String, Failure> {
+    let mut sorted_tasks: Vec<&GeneratedTask> = tasks.iter().collect();
+    sorted_tasks.sort_by(|a, b| a.class_name.cmp(&b.class_name));
+
+    let enums = unique_enums(tasks);
+
+    let enums_code = enums.iter().map(|(e, _)| e.code.as_str()).collect::<Vec<_>>().join("\n\n");
+    let classes_code = sorted_tasks.iter()
+        .map(|t| t.class_code.clone())
+        .chain(crate::ARGS.catalog.as_deref().map(|catalog| catalog_class(tasks, catalog)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let body = format!(
+r#"// --- Enums ---
+
+{enums_code}
+
+{classes_code}"#,
+        enums_code = enums_code,
+        classes_code = classes_code,
+    );
+    let usings: Vec<String> = sorted_tasks.iter().flat_map(|t| t.usings.iter().cloned()).collect();
+    render_file(templates, "tasks", sorted_tasks.iter().map(|t| t.source()).collect(), Vec::new(), &usings, body)
+}
+
+// The enums of all tasks, each once, ordered by name, with the classes using them. Enums of the
+// same name must be identical to be shared; task enums that aren't were already renamed apart,
+// so otherwise the first one (by class name) is kept.
+pub fn unique_enums(tasks: &[GeneratedTask]) -> Vec<(&GeneratedEnum, Vec<&str>)> {
+    let mut sorted_tasks: Vec<&GeneratedTask> = tasks.iter().collect();
+    sorted_tasks.sort_by(|a, b| a.class_name.cmp(&b.class_name));
+
+    let mut enums: BTreeMap<&str, (&GeneratedEnum, Vec<&str>)> = BTreeMap::new();
+    for task in &sorted_tasks {
+        for e in &task.enums {
+            match enums.get_mut(e.name.as_str()) {
+                Some((existing, _)) if existing.code != e.code => {
+                    eprintln!("Warning: Conflicting definitions of enum '{}' (from {}); keeping the first.", e.name, task.class_name);
+                }
+                Some((_, used_by)) => {
+                    if used_by.last() != Some(&task.class_name.as_str()) {
+                        used_by.push(&task.class_name);
+                    }
+                }
+                None => { enums.insert(&e.name, (e, vec![task.class_name.as_str()])); }
+            }
+        }
+    }
+    enums.into_values().collect()
+}
+
+// Renders an enum as a standalone C# file, for --split-enums
+pub fn enum_file(e: &GeneratedEnum, used_by: &[&str], templates: &CodeTemplates) -> Result<String, Failure> {
+    render_file(templates, "enum", Vec::new(), used_by.to_vec(), &e.usings, e.code.clone())
+}
+
+// Renders the enums used by several classes as one C# file, for --out-dir without --split-enums
+pub fn shared_enums_file(enums: &[(&GeneratedEnum, Vec<&str>)], templates: &CodeTemplates) -> Result<String, Failure> {
+    let body = enums.iter()
+        .map(|(e, used_by)| format!("// Used by: {}\n{}", used_by.join(", "), e.code))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let mut used_by: Vec<&str> = enums.iter().flat_map(|(_, used_by)| used_by.iter().copied()).collect();
+    used_by.sort();
+    used_by.dedup();
+    let usings: Vec<String> = enums.iter().flat_map(|(e, _)| e.usings.iter().cloned()).collect();
+    render_file(templates, "enums", Vec::new(), used_by, &usings, body)
+}
+
+// A static class with a factory method per task, ordered by class name, for discovering the
+// generated tasks from a single entry point (--catalog)
+fn catalog_class(tasks: &[GeneratedTask], catalog: &str) -> String {
+    let mut sorted_tasks: Vec<&GeneratedTask> = tasks.iter().collect();
+    sorted_tasks.sort_by(|a, b| a.class_name.cmp(&b.class_name));
+
+    let methods = sorted_tasks.iter()
+        .map(|t| format!(
+            "    /// <summary>\n    /// {source_name}: {summary}\n    /// </summary>\n    public static {class_name} {method_name}() => new();",
+            source_name = t.source_name(),
+            summary = t.summary.lines().map(str::trim).collect::<Vec<_>>().join(" "),
+            class_name = t.class_name,
+            method_name = t.class_name.strip_suffix("Task").filter(|name| !name.is_empty()).unwrap_or(&t.class_name),
+        ))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    format!(
+r#"/// <summary>
+/// The tasks generated by '{tool_name}', one factory method each.
+/// </summary>
+public static class {catalog} {{
+{methods}
+}}
+"#,
+        tool_name = env!("CARGO_PKG_NAME"),
+        catalog = catalog,
+        methods = methods,
+    )
+}
+
+// Renders the --catalog class as a standalone C# file
+pub fn catalog_file(tasks: &[GeneratedTask], catalog: &str, templates: &CodeTemplates) -> Result<String, Failure> {
+    let mut sorted_tasks: Vec<&GeneratedTask> = tasks.iter().collect();
+    sorted_tasks.sort_by(|a, b| a.class_name.cmp(&b.class_name));
+    render_file(templates, "catalog", sorted_tasks.iter().map(|t| t.source()).collect(), Vec::new(), &[], catalog_class(tasks, catalog))
+}
+
+// Renders a file with the file template. The usings are de-duplicated and sorted.
+fn render_file(
+    templates: &CodeTemplates,
+    kind: &'static str,
+    sources: Vec<SourceContext>,
+    used_by: Vec<&str>,
+    usings: &[String],
+    body: String,
+) -> Result<String, Failure> {
+    let mut usings: Vec<&str> = usings.iter().map(String::as_str).collect();
+    usings.sort();
+    usings.dedup();
+
+    templates.render_file(&FileContext {
+        kind,
+        tool_name: env!("CARGO_PKG_NAME"),
+        tool_version: env!("CARGO_PKG_VERSION"),
+        // The generation date is only stamped into headers on request, as it would otherwise
+        // make every regeneration produce a diff
+        generated_at: crate::ARGS.timestamp.then(|| chrono::Local::now().to_rfc2822()),
+        sources,
+        used_by,
+        // The nullable context makes the compiler enforce the '?' annotations on optional inputs
+        nullable: !crate::ARGS.no_nullable,
+        usings,
+        namespace: crate::ARGS.namespace.as_deref(),
+        block_namespace: crate::ARGS.block_namespace,
+        body: body.trim_end().to_string(),
+    })
+}
+
+// Writes a generated file, creating its directory as needed. With --check nothing is written;
+// files whose contents would change are collected into 'drifted' instead.
+pub fn write_or_check(path: &Path, contents: &str, drifted: &mut Vec<PathBuf>) -> Result<(), Failure> {
+    if crate::ARGS.check {
+        if std::fs::read_to_string(path).ok().as_deref() != Some(contents) {
+            drifted.push(path.to_path_buf());
+        }
+        return Ok(());
+    }
+
+    let write = || -> std::io::Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, contents)
+    };
+    write().map_err(|e| Failure::new(FailureKind::Emission, format!("Failed to write {}: {}", path.display(), e)))
+}
+
+// Generated files in the given directories that aren't among those just produced, e.g. those of
+// tasks no longer generated or of renamed classes. They are told apart from hand-written files
+// (like --partial companions) by the header the built-in file templates start them with.
+pub fn stale_files(dirs: &[&Path], produced: &[PathBuf]) -> Vec<PathBuf> {
+    let header = format!("// Auto-Generated using '{}'", env!("CARGO_PKG_NAME"));
+    let mut stale: Vec<PathBuf> = dirs.iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "cs") && !produced.contains(path))
+        .filter(|path| std::fs::read_to_string(path).is_ok_and(|contents| contents.starts_with(&header)))
+        .collect();
+    stale.sort();
+    stale.dedup();
+    stale
+}
+
+// Removes a generated file that is no longer produced. With --check it is collected into
+// 'drifted' instead, as the committed output has a file too many.
+pub fn remove_or_check(path: &Path, drifted: &mut Vec<PathBuf>) -> Result<(), Failure> {
+    if crate::ARGS.check {
+        drifted.push(path.to_path_buf());
+        return Ok(());
+    }
+    std::fs::remove_file(path)
+        .map_err(|e| Failure::new(FailureKind::Emission, format!("Failed to remove {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(class_name: &str) -> GeneratedTask {
+        GeneratedTask {
+            class_name: class_name.to_string(),
+            task_name: "Npm".to_string(),
+            task_version: "1".to_string(),
+            documentation_url: "https://example.invalid/npm-v1".to_string(),
+            summary: "Install npm packages.".to_string(),
+            usings: vec!["YamlDotNet.Serialization".to_string(), "Sharpliner.AzureDevOps.Tasks".to_string(), "YamlDotNet.Serialization".to_string()],
+            enums: Vec::new(),
+            class_code: format!("public record class {} : AzureDevOpsTask {{\n}}\n", class_name),
+        }
+    }
+
+    #[test]
+    fn files_are_laid_out_by_the_file_template() {
+        let file = task("NpmTask").class_file(&CodeTemplates::builtin()).unwrap();
+        assert!(file.starts_with(&format!("// Auto-Generated using '{}' version {}\n", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))));
+        assert!(file.ends_with(
+            "// Source Task: Npm v1\n// Source Documentation: https://example.invalid/npm-v1\n\n#nullable enable\n\n\
+using Sharpliner.AzureDevOps.Tasks;\nusing YamlDotNet.Serialization;\n\npublic record class NpmTask : AzureDevOpsTask {\n}\n"
+        ));
+
+        // A template set can give its files a header of its own
+        let templates = CodeTemplates::builtin().with_template(
+            "file",
+            "// <copyright>Contoso</copyright>\n// {{ kind }}: {% for source in sources %}{{ source.class_name }} {% endfor %}\n{% for using in usings %}using {{ using }};\n{% endfor %}\n{{ body }}",
+        );
+        assert_eq!(
+            merge_tasks(&[task("NpmTask"), task("AnotherTask")], &templates).unwrap().lines().take(4).collect::<Vec<_>>(),
+            ["// <copyright>Contoso</copyright>", "// tasks: AnotherTask NpmTask ", "using Sharpliner.AzureDevOps.Tasks;", "using YamlDotNet.Serialization;"]
+        );
+    }
+
+    #[test]
+    fn generated_files_no_longer_produced_are_stale() {
+        let dir = std::env::temp_dir().join(format!("{}-stale-{}", env!("CARGO_PKG_NAME"), std::process::id()));
+        let enums_dir = dir.join("Enums");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&enums_dir).unwrap();
+
+        let templates = CodeTemplates::builtin();
+        let produced = [dir.join("NpmTask.cs"), enums_dir.join("Command.cs")];
+        std::fs::write(&produced[0], task("NpmTask").class_file(&templates).unwrap()).unwrap();
+        std::fs::write(&produced[1], "// Auto-Generated using 'sharpliner_task_codegen' version 0.1.0\n").unwrap();
+        std::fs::write(dir.join("OldTask.cs"), task("OldTask").class_file(&templates).unwrap()).unwrap();
+        std::fs::write(enums_dir.join("Registry.cs"), "// Auto-Generated using 'sharpliner_task_codegen' version 0.1.0\n").unwrap();
+        // Hand-written and non-C# files are never stale
+        std::fs::write(dir.join("NpmTask.Partial.cs"), "public partial record class NpmTask {\n}\n").unwrap();
+        std::fs::write(dir.join("README.md"), "// Auto-Generated using 'sharpliner_task_codegen'\n").unwrap();
+
+        assert_eq!(stale_files(&[&dir, &enums_dir], &produced), [enums_dir.join("Registry.cs"), dir.join("OldTask.cs")]);
+        assert_eq!(stale_files(&[&dir, &dir], &produced), [dir.join("OldTask.cs")]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn namespaces_are_file_scoped_unless_a_block_is_asked_for() {
+        let file = |namespace, block_namespace| CodeTemplates::builtin().render_file(&FileContext {
+            kind: "enums",
+            tool_name: env!("CARGO_PKG_NAME"),
+            tool_version: env!("CARGO_PKG_VERSION"),
+            generated_at: None,
+            sources: Vec::new(),
+            used_by: Vec::new(),
+            nullable: false,
+            usings: vec!["YamlDotNet.Serialization"],
+            namespace,
+            block_namespace,
+            body: "public enum Command {\n    Install,\n}\n\npublic enum Registry {\n}".to_string(),
+        }).unwrap();
+
+        assert!(file(None, false).ends_with("using YamlDotNet.Serialization;\n\npublic enum Command {\n    Install,\n}\n\npublic enum Registry {\n}\n"));
+        assert!(file(Some("Contoso.Pipelines.Tasks"), false).ends_with(
+            "using YamlDotNet.Serialization;\n\nnamespace Contoso.Pipelines.Tasks;\n\npublic enum Command {\n    Install,\n}\n\npublic enum Registry {\n}\n"
+        ));
+        // Blank lines inside the block stay empty rather than indented
+        assert!(file(Some("Contoso.Pipelines.Tasks"), true).ends_with(
+            "using YamlDotNet.Serialization;\n\nnamespace Contoso.Pipelines.Tasks\n{\n    public enum Command {\n        Install,\n    }\n\n    public enum Registry {\n    }\n}\n"
+        ));
+    }
+}
@@ -0,0 +1,209 @@
+// --- Docs Page Sections ---
+// Besides the snippet and the Inputs section, a task's docs page has sections describing the
+// task as a whole. Like the Inputs section, each is an h2 with a known id followed by
+// paragraphs (or a table), e.g. for output variables:
+//   <p><strong><code>NuGetToolPath</code></strong><br/>
+//   Specifies the path to the NuGet tool.</p>
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::{OutputVariable, TaskRequirements};
+
+lazy_static! {
+    static ref AGENT_VERSION_RE: Regex = Regex::new(
+        r"\d+(?:\.\d+)+"
+    ).expect("Invalid Agent Version Regex");
+
+    // Inline code in a paragraph, kept as a `code` span
+    static ref CODE_SPAN_RE: Regex = Regex::new(
+        r"(?s)<code[^>]*>(?<Code>.*?)</code>"
+    ).expect("Invalid Code Span Regex");
+
+    // Only absolute links are kept; relative ones point into the docs site
+    static ref LINK_RE: Regex = Regex::new(
+        r#"(?s)<a [^>]*href="(?<Url>https?://[^"]+)"[^>]*>(?<Text>.*?)</a>"#
+    ).expect("Invalid Link Regex");
+
+    // A task reference in a page heading or title, e.g. 'Npm@1 - npm v1 task'
+    static ref TASK_REFERENCE_RE: Regex = Regex::new(
+        r"\b(?<TaskName>[A-Za-z]\w*)@(?<TaskVersion>\d+)\b"
+    ).expect("Invalid Task Reference Regex");
+}
+
+// Inner HTML of the paragraphs (or other blocks, e.g. "li") under the h2 with the given id, in page order
+fn section_paragraphs(document: &Html, section_id: &str, blocks: &str) -> Vec<String> {
+    let Ok(selector) = Selector::parse(&format!("div.content h2, div.content {}", blocks)) else {
+        return Vec::new();
+    };
+
+    let mut paragraphs = Vec::new();
+    let mut in_section = false;
+    for element in document.select(&selector) {
+        if element.value().name() == "h2" {
+            in_section = element.value().id() == Some(section_id);
+        } else if in_section {
+            paragraphs.push(element.inner_html());
+        }
+    }
+    paragraphs
+}
+
+// The output variables listed in the page's Output variables section
+pub fn parse_output_variables(html: &str) -> Vec<OutputVariable> {
+    let document = Html::parse_document(html);
+    let mut variables: Vec<OutputVariable> = Vec::new();
+
+    for paragraph in section_paragraphs(&document, "output-variables", "p") {
+        let fragment = Html::parse_fragment(&paragraph);
+        let name = Selector::parse("strong > code").ok()
+            .and_then(|selector| fragment.select(&selector).next().map(|code| element_text(&code)))
+            .filter(|_| paragraph.trim_start().starts_with("<strong><code>"));
+
+        match name {
+            Some(name) => {
+                // The description follows the name, on the same paragraph or the next ones
+                let description = paragraph.split_once("</strong>")
+                    .map(|(_, rest)| fragment_text(rest))
+                    .unwrap_or_default();
+                variables.push(OutputVariable { name, description });
+            }
+            None => if let Some(variable) = variables.last_mut() {
+                let text = fragment_text(&paragraph);
+                variable.description = [variable.description.as_str(), text.as_str()].join(" ").trim().to_string();
+            },
+        }
+    }
+
+    variables
+}
+
+// The Remarks section's paragraphs and list items, as markdown-ish text keeping code spans and links
+pub fn parse_remarks(html: &str) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let mut remarks: Vec<String> = section_paragraphs(&document, "remarks", "p, li").iter()
+        .map(|block| {
+            let block = CODE_SPAN_RE.replace_all(block, "`$Code`");
+            let block = LINK_RE.replace_all(&block, "[$Text]($Url)");
+            fragment_text(&block)
+        })
+        .filter(|text| !text.is_empty())
+        .collect();
+    // A list item wrapping a single paragraph is selected twice
+    remarks.dedup();
+    remarks
+}
+
+// The YAML snippets of the Examples section, as written
+pub fn parse_examples(html: &str) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let Ok(selector) = Selector::parse("div.content h2, div.content pre code") else {
+        return Vec::new();
+    };
+
+    let mut examples = Vec::new();
+    let mut in_section = false;
+    for element in document.select(&selector) {
+        if element.value().name() == "h2" {
+            in_section = element.value().id() == Some("examples");
+        } else if in_section && !element.value().classes().any(|class| class.starts_with("lang-") && class != "lang-yaml") {
+            // Examples in other languages (e.g. a PowerShell script the pipeline runs) are skipped
+            let code = element.text().collect::<String>();
+            let code = code.trim_matches('\n').trim_end();
+            if !code.is_empty() {
+                examples.push(code.to_string());
+            }
+        }
+    }
+    examples
+}
+
+// The Requirements section's table, e.g.
+//   <tr><td>Demands</td><td>Self-hosted agents must have capabilities that match the following demands to run jobs that use this task: npm</td></tr>
+//   <tr><td>Agent version</td><td>2.115.0 or greater</td></tr>
+pub fn parse_requirements(html: &str) -> TaskRequirements {
+    let document = Html::parse_document(html);
+    let mut requirements = TaskRequirements::default();
+    let (Ok(selector), Ok(cell_selector)) = (Selector::parse("div.content h2, div.content tr"), Selector::parse("td, th")) else {
+        return requirements;
+    };
+
+    let mut in_section = false;
+    for element in document.select(&selector) {
+        if element.value().name() == "h2" {
+            in_section = element.value().id() == Some("requirements");
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+
+        let cells: Vec<String> = element.select(&cell_selector).map(|cell| element_text(&cell)).collect();
+        let [requirement, value] = cells.as_slice() else {
+            continue;
+        };
+        match requirement.as_str() {
+            "Demands" => {
+                // Either 'None', or a sentence ending in the list of demands
+                let demands = value.rsplit_once(':').map_or(value.as_str(), |(_, demands)| demands);
+                requirements.demands = demands.split(',')
+                    .map(|d| d.trim().trim_end_matches('.').to_string())
+                    .filter(|d| !d.is_empty() && !d.eq_ignore_ascii_case("none"))
+                    .collect();
+            }
+            "Agent version" => requirements.minimum_agent_version = AGENT_VERSION_RE.find(value).map(|v| v.as_str().to_string()),
+            _ => {}
+        }
+    }
+
+    requirements
+}
+
+// The task name and major version the page's heading (or, failing that, its title) is about
+pub fn page_task_reference(html: &str) -> Option<(String, String)> {
+    let document = Html::parse_document(html);
+    ["h1", "title"].into_iter()
+        .filter_map(|tag| Selector::parse(tag).ok())
+        .find_map(|selector| {
+            let text = document.select(&selector).next().map(|e| element_text(&e))?;
+            TASK_REFERENCE_RE.captures(&text).map(|caps| (caps["TaskName"].to_string(), caps["TaskVersion"].to_string()))
+        })
+}
+
+fn element_text(element: &ElementRef) -> String {
+    element.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Plain text of an HTML fragment
+fn fragment_text(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    element_text(&fragment.root_element())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_variables_are_read_from_their_section() {
+        let html = r#"<html><body><div class="content">
+<h2 id="inputs">Inputs</h2>
+<p><strong><code>command</code></strong> - <strong>Command</strong></p>
+<h2 id="output-variables">Output variables</h2>
+<p><strong><code>NuGetToolPath</code></strong><br/>The path to the <code>nuget.exe</code> that was installed.</p>
+<p>Set on Windows agents only.</p>
+<p><strong><code>NuGetVersion</code></strong><br/>The installed version.</p>
+<h2 id="remarks">Remarks</h2>
+<p><strong><code>NotAnOutput</code></strong> is described elsewhere.</p>
+</div></body></html>"#;
+        let variables = parse_output_variables(html);
+        let variables: Vec<(&str, &str)> = variables.iter().map(|v| (v.name.as_str(), v.description.as_str())).collect();
+        assert_eq!(variables, [
+            ("NuGetToolPath", "The path to the nuget.exe that was installed. Set on Windows agents only."),
+            ("NuGetVersion", "The installed version."),
+        ]);
+
+        assert!(parse_output_variables("<html><body><div class=\"content\"><h2 id=\"output-variables\">Output variables</h2><p>None.</p></div></body></html>").is_empty());
+    }
+}
@@ -0,0 +1,55 @@
+// --- Parse Report ---
+// The parse diagnostics of every task as JSON (--parse-report). Errors are snippet lines the
+// parser could not make sense of: in the default tolerant mode they are skipped, and with
+// --strict any of them fails the task.
+
+use serde::Serialize;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::TaskDefinition;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportTask<'a> {
+    task_name: &'a str,
+    task_version: &'a str,
+    documentation_url: &'a str,
+    diagnostics: Vec<ReportDiagnostic<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportDiagnostic<'a> {
+    severity: Severity,
+    message: &'a str,
+    line: usize,         // 1-based line number within the snippet
+    column_start: usize, // 1-based, inclusive
+    column_end: usize,   // 1-based, inclusive
+    text: &'a str,       // The line as it appears in the snippet
+}
+
+// Only tasks with diagnostics are listed
+pub fn render_parse_report(tasks: &[TaskDefinition]) -> Result<String, serde_json::Error> {
+    let report: Vec<ReportTask> = tasks.iter()
+        .filter(|t| !t.info.diagnostics.is_empty())
+        .map(|t| ReportTask {
+            task_name: &t.info.task_name,
+            task_version: &t.info.task_version,
+            documentation_url: &t.documentation_url,
+            diagnostics: t.info.diagnostics.iter().map(report_diagnostic).collect(),
+        })
+        .collect();
+    serde_json::to_string_pretty(&report)
+}
+
+fn report_diagnostic(diagnostic: &Diagnostic) -> ReportDiagnostic<'_> {
+    let (column_start, column_end) = diagnostic.column_range();
+    ReportDiagnostic {
+        severity: diagnostic.severity,
+        message: &diagnostic.message,
+        line: diagnostic.line,
+        column_start,
+        column_end,
+        text: &diagnostic.text,
+    }
+}
@@ -0,0 +1,62 @@
+// --- Pipeline Task References ---
+// Finds the tasks an existing pipeline uses ('- task: NuGetCommand@2'), which is usually how
+// users discover which typed tasks they need.
+
+use regex::Regex;
+use lazy_static::lazy_static;
+
+use crate::deprecation::TaskReference;
+
+lazy_static! {
+    // A step's task reference, e.g. "- task: NuGetCommand@2" or "task: 'Npm@1'"
+    static ref TASK_REFERENCE_RE: Regex = Regex::new(
+        r#"(?m)^\s*(?:-\s*)?task:\s*['"]?(?<TaskName>[A-Za-z][\w.-]*)@(?<TaskVersion>\d+)['"]?\s*(?:#.*)?$"#
+    ).expect("Invalid Pipeline Task Reference Regex");
+}
+
+// Every distinct task referenced in a pipeline YAML, in order of first use
+pub fn task_references(yaml: &str) -> Vec<TaskReference> {
+    let mut references: Vec<TaskReference> = Vec::new();
+    for caps in TASK_REFERENCE_RE.captures_iter(yaml) {
+        let reference = TaskReference {
+            task_name: caps["TaskName"].to_string(),
+            task_version: caps["TaskVersion"].to_string(),
+        };
+        // Task names are case-insensitive
+        let seen = references.iter().any(|r| {
+            r.task_name.eq_ignore_ascii_case(&reference.task_name) && r.task_version == reference.task_version
+        });
+        if !seen {
+            references.push(reference);
+        }
+    }
+    references
+}
+
+// Tasks of Marketplace extensions are referenced by a qualified name, e.g.
+// 'SonarSource.sonarcloud.SonarCloudPrepare@1', and built-in tasks can be by their GUID. Neither
+// has a page in the task reference docs, which only name built-in tasks, by a plain identifier.
+pub fn has_docs_page(task_name: &str) -> bool {
+    task_name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extension_tasks_have_no_docs_page() {
+        let references = task_references("steps:
+- task: SonarSource.sonarcloud.SonarCloudPrepare@1
+- task: 'NuGetCommand@2'
+- task: e213ff0f-5d5c-4791-802d-52ea3e7be1f1@2 # PowerShell, by its GUID
+- task: nugetcommand@2
+");
+        let documented: Vec<(&str, bool)> = references.iter().map(|r| (r.task_name.as_str(), has_docs_page(&r.task_name))).collect();
+        assert_eq!(documented, [
+            ("SonarSource.sonarcloud.SonarCloudPrepare", false),
+            ("NuGetCommand", true),
+            ("e213ff0f-5d5c-4791-802d-52ea3e7be1f1", false),
+        ]);
+    }
+}
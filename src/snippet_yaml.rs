@@ -0,0 +1,141 @@
+// --- Snippet YAML Lines ---
+// The YAML of a docs snippet documents its inputs in comments, which YAML parsers throw away,
+// so each line is tokenized here instead: its indentation, key, value and comment, with quoted
+// values kept whole. The snippet parser follows the structure (indentation depth, nested values,
+// block scalars) from these, rather than assuming a fixed indentation. e.g.
+//     #command: 'install' # 'ci' | 'install'. Required. Command. Default: install.
+// is the commented-out key 'command' at indent 4, with value "'install'" and the comment
+// "'ci' | 'install'. Required. Command. Default: install."
+
+use std::ops::Range;
+
+pub struct YamlLine<'a> {
+    pub indent: usize,
+    pub key: Option<Key<'a>>,
+    pub value: &'a str, // After the key, without the comment; the whole line if there's no key
+    pub comment: Option<Comment<'a>>,
+}
+
+pub struct Key<'a> {
+    pub name: &'a str,
+    pub columns: Range<usize>, // Byte range of the name within the line
+    pub commented_out: bool,   // Written as '#key: ...', as snippets do for optional inputs
+}
+
+pub struct Comment<'a> {
+    pub text: &'a str, // Trimmed, without the '#'
+    pub start: usize,  // Byte offset of the text within the line
+}
+
+impl YamlLine<'_> {
+    pub fn is_blank(&self) -> bool {
+        self.key.is_none() && self.value.is_empty() && self.comment.is_none()
+    }
+
+    // A comment on a line of its own
+    pub fn is_comment_only(&self) -> bool {
+        self.key.is_none() && self.value.is_empty() && self.comment.is_some()
+    }
+
+    // 'key: |' or 'key: >-': the value continues on the lines indented below
+    pub fn is_block_scalar(&self) -> bool {
+        self.key.is_some() && (self.value.starts_with('|') || self.value.starts_with('>'))
+    }
+}
+
+pub fn tokenize(line: &str) -> YamlLine<'_> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+
+    if let Some(commented) = rest.strip_prefix('#') {
+        // '#key: value # documentation' is a commented-out key; '# key: value' only with its own
+        // comment, since '# Note: ...' is just prose
+        let commented_start = indent + 1;
+        let key_start = commented_start + (commented.len() - commented.trim_start().len());
+        if let Some(line) = keyed_line(line, indent, key_start, true)
+            && (key_start == commented_start || line.comment.is_some())
+        {
+            return line;
+        }
+        return YamlLine { indent, key: None, value: "", comment: comment_at(line, commented_start) };
+    }
+
+    // A list item's key, e.g. '- task: Npm@1'
+    let key_start = match rest.strip_prefix("- ") {
+        Some(item) => indent + 2 + (item.len() - item.trim_start().len()),
+        None => indent,
+    };
+    keyed_line(line, indent, key_start, false).unwrap_or_else(|| {
+        let (value, comment) = split_comment(line, indent);
+        YamlLine { indent, key: None, value, comment }
+    })
+}
+
+fn keyed_line(line: &str, indent: usize, key_start: usize, commented_out: bool) -> Option<YamlLine<'_>> {
+    let text = &line[key_start..];
+    let mut chars = text.char_indices();
+    if !chars.next().is_some_and(|(_, c)| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    let key_end = chars.find(|(_, c)| !(c.is_alphanumeric() || "_.-".contains(*c))).map_or(text.len(), |(i, _)| i);
+    let after_key = &text[key_end..];
+    let after_colon = after_key.strip_prefix(':')?;
+    if !after_colon.is_empty() && !after_colon.starts_with(char::is_whitespace) {
+        return None; // e.g. 'https://...'
+    }
+
+    let (value, comment) = split_comment(line, key_start + key_end + 1);
+    Some(YamlLine {
+        indent,
+        key: Some(Key { name: &text[..key_end], columns: key_start..key_start + key_end, commented_out }),
+        value,
+        comment,
+    })
+}
+
+// The value starting at byte 'start' of the line, and the comment after it. A '#' only starts a
+// comment after whitespace, and never inside a quoted value.
+fn split_comment(line: &str, start: usize) -> (&str, Option<Comment<'_>>) {
+    let text = &line[start..];
+    let value_start = text.len() - text.trim_start().len();
+    let search_from = match text[value_start..].chars().next() {
+        Some(quote @ ('\'' | '"')) => closing_quote(&text[value_start + 1..], quote).map_or(text.len(), |end| value_start + 1 + end + 1),
+        _ => value_start,
+    };
+
+    let comment = text[search_from..].char_indices()
+        .find(|&(i, c)| {
+            let position = search_from + i;
+            c == '#' && (position == 0 || text[..position].ends_with(char::is_whitespace))
+        })
+        .map(|(i, _)| search_from + i);
+    match comment {
+        Some(position) => (text[..position].trim(), comment_at(line, start + position + 1)),
+        None => (text.trim(), None),
+    }
+}
+
+// Where a quoted value ends; '' escapes a single quote, and \" a double one
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if quote == '"' && c == '\\' {
+            chars.next();
+        } else if c == quote {
+            if quote == '\'' && chars.peek().is_some_and(|&(_, next)| next == '\'') {
+                chars.next();
+                continue;
+            }
+            return Some(i);
+        }
+    }
+    None
+}
+
+// The comment whose text starts at byte 'start' of the line (just after its '#')
+fn comment_at(line: &str, start: usize) -> Option<Comment<'_>> {
+    let text = &line[start..];
+    let leading = text.len() - text.trim_start().len();
+    let text = text.trim();
+    (!text.is_empty()).then(|| Comment { text, start: start + leading })
+}
@@ -0,0 +1,99 @@
+// --- Step Factories ---
+// With --step-factories, each task also gets a static factory method on a partial 'Steps' class,
+// e.g. Steps.Npm(command: Command.Ci, workingDir: "src"), for pipelines written as method calls
+// rather than object initializers. Parameters follow the documentation order, except that inputs
+// which must always be given come first, as C# requires of parameters without a default.
+// Optional inputs default to null and are only set when given, so the YAML stays as short as
+// with an initializer.
+
+use heck::ToLowerCamelCase;
+
+use crate::{documentation_escaped, service_connection_type, ProcessedParameter, CSHARP_KEYWORDS};
+
+// The factory method of one task, in its own part of the partial factory class
+pub fn step_factory(factory_class: &str, class_name: &str, summary: &str, params: &[ProcessedParameter]) -> String {
+    let method_name = class_name.strip_suffix("Task").filter(|name| !name.is_empty()).unwrap_or(class_name);
+    let is_required = |p: &&ProcessedParameter| !p.is_nullable && p.getter_default_arg.is_none();
+    let (required, optional): (Vec<&ProcessedParameter>, Vec<&ProcessedParameter>) = params.iter().partition(is_required);
+
+    let summary = summary.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ");
+    let mut docs = format!("    /// <summary>\n    /// Creates a <see cref=\"{}\"/> step. {}\n    /// </summary>\n", class_name, documentation_escaped(&summary));
+    for p in required.iter().chain(&optional) {
+        let description = p.description.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+        docs.push_str(&format!("    /// <param name=\"{}\">{}</param>\n", parameter_name(p), documentation_escaped(&description)));
+    }
+
+    if params.is_empty() {
+        return format!(
+            "public static partial class {factory_class} {{\n{docs}    public static {class_name} {method_name}() => new();\n}}\n",
+            factory_class = factory_class,
+            docs = docs,
+            class_name = class_name,
+            method_name = method_name,
+        );
+    }
+
+    // Optional inputs are nullable parameters, whatever the type of their property; filePath
+    // inputs take strings, which convert to the --file-path-type implicitly, but service
+    // connections take the --service-connection-type, which strings don't
+    let parameters = required.iter()
+        .map(|p| format!("        {} {}", service_connection_type(p).unwrap_or(&p.csharp_type), parameter_name(p)))
+        .chain(optional.iter().map(|p| {
+            let csharp_type = match service_connection_type(p) {
+                Some(service_connection_type) => format!("{}?", service_connection_type),
+                None if p.is_nullable => p.csharp_type.clone(),
+                None => format!("{}?", p.base_csharp_type),
+            };
+            format!("        {} {} = null", csharp_type, parameter_name(p))
+        }))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    let initializer = match required.as_slice() {
+        [] => String::new(),
+        required => format!(
+            " {{ {} }}",
+            required.iter().map(|p| format!("{} = {}", p.csharp_name, parameter_name(p))).collect::<Vec<_>>().join(", ")
+        ),
+    };
+    let optional_assignments: String = optional.iter()
+        .map(|p| {
+            // A nullable value type has to be unwrapped for a property that isn't nullable
+            let value = if !p.is_nullable && p.base_csharp_type != "string" { ".Value" } else { "" };
+            let name = parameter_name(p);
+            format!("        if ({name} is not null) step = step with {{ {} = {name}{} }};\n", p.csharp_name, value, name = name)
+        })
+        .collect();
+
+    format!(
+r#"public static partial class {factory_class} {{
+{docs}    public static {class_name} {method_name}(
+{parameters})
+    {{
+        var step = new {class_name}(){initializer};
+{optional_assignments}        return step;
+    }}
+}}
+"#,
+        factory_class = factory_class,
+        docs = docs,
+        class_name = class_name,
+        method_name = method_name,
+        parameters = parameters,
+        initializer = initializer,
+        optional_assignments = optional_assignments,
+    )
+}
+
+// camelCase, escaped like property names, and never 'step', the factory's own variable
+fn parameter_name(p: &ProcessedParameter) -> String {
+    let name = p.csharp_name.trim_start_matches('@').to_lower_camel_case();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else if CSHARP_KEYWORDS.contains(name.as_str()) {
+        format!("@{}", name)
+    } else if name == "step" {
+        "stepInput".to_string()
+    } else {
+        name
+    }
+}
@@ -0,0 +1,163 @@
+// --- Task Group Input Source ---
+// A task group exported from Azure DevOps is a JSON document much like a task.json: its
+// parameters are declared as 'inputs' in the same format, followed by the steps it runs.
+// YAML pipelines can't reference a task group, so the generated class can't be a step itself.
+// Instead each enabled step becomes a nested class referencing its task by id, with the
+// step's inputs set as exported, and the class's 'Steps' gives them with the references to
+// the group's parameters ('$(name)') replaced by the values set on the instance.
+
+use heck::ToPascalCase;
+use serde_json::Value;
+
+use crate::task_json;
+use crate::{csharp_string_literal, documentation_escaped, ParsedTaskInfo, ProcessedParameter};
+
+pub struct TaskGroup {
+    pub info: ParsedTaskInfo,
+    pub display_name: String,
+    pub steps: Vec<GroupStep>,
+}
+
+// An enabled step of a task group
+#[derive(Debug, Clone)]
+pub struct GroupStep {
+    pub display_name: String,
+    pub task_id: String,
+    pub major_version: Option<String>, // From the version spec, e.g. '2' for '2.*'
+    pub inputs: Vec<(String, String)>, // As exported, possibly referencing the group's parameters
+}
+
+pub fn parse_task_group(json: &str) -> Result<TaskGroup, Box<dyn std::error::Error>> {
+    let group: Value = serde_json::from_str(json)?;
+
+    let display_name = group["name"].as_str().ok_or("task group has no 'name'")?.trim().to_string();
+    // The name ends up in the class's task reference, so it has to be an identifier
+    let task_name = display_name.to_pascal_case();
+    if task_name.is_empty() {
+        return Err(format!("task group name '{}' has no letters or digits", display_name).into());
+    }
+    let task_version = group["version"]["major"].as_u64().unwrap_or(1).to_string();
+    let task_summary = group["description"].as_str().map(str::trim).filter(|d| !d.is_empty()).unwrap_or("N/A").to_string();
+
+    let parameters = group["inputs"].as_array()
+        .map(|inputs| inputs.iter().filter_map(task_json::parse_input).collect())
+        .unwrap_or_default();
+
+    let steps = group["tasks"].as_array()
+        .map(|tasks| tasks.iter()
+            .filter(|step| step["enabled"].as_bool() != Some(false))
+            .map(parse_step)
+            .collect::<Result<Vec<_>, _>>())
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(TaskGroup {
+        info: ParsedTaskInfo { task_summary, task_name, task_version, parameters, diagnostics: Vec::new() },
+        display_name,
+        steps,
+    })
+}
+
+fn parse_step(step: &Value) -> Result<GroupStep, Box<dyn std::error::Error>> {
+    let task_id = step["task"]["id"].as_str().ok_or("a step of the task group has no 'task.id'")?.to_string();
+    let major_version = step["task"]["versionSpec"].as_str()
+        .map(|spec| spec.split('.').next().unwrap_or_default())
+        .filter(|major| !major.is_empty() && major.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_string);
+    let inputs = step["inputs"].as_object()
+        .map(|inputs| inputs.iter()
+            .filter_map(|(name, value)| {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    Value::Null => return None,
+                    value => value.to_string(),
+                };
+                Some((name.clone(), value)).filter(|(_, value)| !value.is_empty())
+            })
+            .collect())
+        .unwrap_or_default();
+
+    Ok(GroupStep {
+        display_name: step["displayName"].as_str().map(str::trim).unwrap_or("Unnamed step").to_string(),
+        task_id,
+        major_version,
+        inputs,
+    })
+}
+
+// Why the class isn't a step itself, for its remarks
+pub fn remark(display_name: &str) -> String {
+    format!(
+        "'{}' is a task group, which YAML pipelines can't reference, so this class can't be added to a \
+pipeline as a step itself; add its <see cref=\"Steps\"/> instead.",
+        documentation_escaped(display_name)
+    )
+}
+
+// The nested step classes, the 'Steps' property and the 'Expand' helper they share
+pub fn steps_code(steps: &[GroupStep], params: &[ProcessedParameter], string_accessor: &str) -> String {
+    let step_classes = steps.iter().enumerate()
+        .map(|(i, step)| {
+            let reference = match &step.major_version {
+                Some(major) => format!("{}@{}", step.task_id, major),
+                None => step.task_id.clone(),
+            };
+            let inputs: String = step.inputs.iter()
+                .map(|(name, value)| format!(
+                    "            SetProperty({}, expand({}));\n",
+                    csharp_string_literal(name),
+                    csharp_string_literal(value),
+                ))
+                .collect();
+            format!(
+r#"    /// <summary>
+    /// Step {number} of the group: {display_name}
+    /// </summary>
+    public record class Step{number} : AzureDevOpsTask {{
+        public Step{number}(Func<string, string> expand) : base({reference})
+        {{
+{inputs}        }}
+    }}
+
+"#,
+                number = i + 1,
+                display_name = documentation_escaped(&step.display_name),
+                reference = csharp_string_literal(&reference),
+                inputs = inputs,
+            )
+        })
+        .collect::<String>();
+
+    let step_instances = (1..=steps.len())
+        .map(|number| format!("        new Step{}(Expand),\n", number))
+        .collect::<String>();
+    let replacements = params.iter()
+        .map(|p| {
+            let reference = csharp_string_literal(&format!("$({})", p.yaml_name));
+            format!(
+                "\n        .Replace({reference}, {accessor}({name}) ?? {reference})",
+                reference = reference,
+                accessor = string_accessor,
+                name = csharp_string_literal(&p.yaml_name),
+            )
+        })
+        .collect::<String>();
+
+    format!(
+r#"{step_classes}    /// <summary>
+    /// The steps the task group runs, in order, with references to its parameters replaced by
+    /// the values set on this instance.
+    /// </summary>
+    [YamlIgnore]
+    public AzureDevOpsTask[] Steps => new AzureDevOpsTask[]
+    {{
+{step_instances}    }};
+
+    private string Expand(string value) => value{replacements};
+
+"#,
+        step_classes = step_classes,
+        step_instances = step_instances,
+        replacements = replacements,
+    )
+}
@@ -0,0 +1,247 @@
+// --- task.json Input Source ---
+// task.json is the structured definition Azure DevOps itself uses for a task (see
+// microsoft/azure-pipelines-tasks). Unlike the docs snippet it has authoritative types,
+// defaults and picklist options, so nothing has to be guessed.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::deprecation::{self, Deprecation};
+use crate::{build_parameter, csharp_identifier, InputKind, OutputVariable, ParsedTaskInfo, ProcessedParameter, TaskRequirements};
+
+lazy_static! {
+    // Version suffix of a task folder in azure-pipelines-tasks, e.g. the 'V1' in 'Tasks/NpmV1/task.json'
+    static ref FOLDER_VERSION_RE: Regex = Regex::new(
+        r"V\d+(?<Suffix>[/\\]task\.json)$"
+    ).expect("Invalid Task Folder Version Regex");
+
+    // A Markdown link in helpMarkDown, e.g. '[More information](https://go.microsoft.com/fwlink/?LinkID=613746)'
+    static ref MARKDOWN_LINK_RE: Regex = Regex::new(
+        r"\[(?<Text>[^\]]*)\]\((?<Url>[^)\s]+)\)"
+    ).expect("Invalid Markdown Link Regex");
+
+    static ref MARKDOWN_CODE_RE: Regex = Regex::new(
+        r"`(?<Code>[^`]+)`"
+    ).expect("Invalid Markdown Code Regex");
+}
+
+pub struct TaskJson {
+    pub info: ParsedTaskInfo,
+    pub deprecation: Option<Deprecation>,
+    pub output_variables: Vec<OutputVariable>,
+    pub requirements: TaskRequirements,
+    pub instance_name_format: Option<String>,
+    pub task_id: Option<String>,
+}
+
+pub fn parse_task_json(json: &str) -> Result<TaskJson, Box<dyn std::error::Error>> {
+    let task: Value = serde_json::from_str(json)?;
+
+    let task_name = task["name"].as_str().ok_or("task.json has no 'name'")?.to_string();
+    // Major is a number in most task.json files, but a string in some
+    let task_version = match &task["version"]["Major"] {
+        Value::String(major) => major.clone(),
+        major => major.as_u64().ok_or("task.json has no 'version.Major'")?.to_string(),
+    };
+    let task_summary = task["description"].as_str().unwrap_or("N/A").trim().to_string();
+
+    let parameters = task["inputs"].as_array()
+        .map(|inputs| inputs.iter().filter_map(|input| {
+            let mut parameter = parse_input(input)?;
+            parameter.group = group_display_name(&task, input);
+            Some(parameter)
+        }).collect())
+        .unwrap_or_default();
+
+    let deprecation = task["deprecated"].as_bool()
+        .filter(|deprecated| *deprecated)
+        .map(|_| deprecation::from_message(task["deprecationMessage"].as_str().unwrap_or("")));
+
+    let output_variables = task["outputVariables"].as_array()
+        .map(|variables| variables.iter().filter_map(|v| Some(OutputVariable {
+            name: v["name"].as_str()?.to_string(),
+            description: v["description"].as_str().unwrap_or("").trim().to_string(),
+        })).collect())
+        .unwrap_or_default();
+
+    let requirements = TaskRequirements {
+        demands: task["demands"].as_array()
+            .map(|demands| demands.iter().filter_map(|d| d.as_str()).map(str::to_string).collect())
+            .unwrap_or_default(),
+        minimum_agent_version: task["minimumAgentVersion"].as_str().filter(|v| !v.is_empty()).map(str::to_string),
+    };
+
+    Ok(TaskJson {
+        info: ParsedTaskInfo { task_summary, task_name, task_version, parameters, diagnostics: Vec::new() },
+        deprecation,
+        output_variables,
+        requirements,
+        task_id: task["id"].as_str().map(str::trim).filter(|id| !id.is_empty()).map(str::to_string),
+        instance_name_format: task["instanceNameFormat"].as_str().map(str::trim).filter(|f| !f.is_empty()).map(str::to_string),
+    })
+}
+
+pub fn parse_input(input: &Value) -> Option<ProcessedParameter> {
+    let yaml_name = input["name"].as_str()?;
+    let input_type = input["type"].as_str().unwrap_or("string");
+    let label = input["label"].as_str().unwrap_or(yaml_name).trim();
+    let visible_rule = input["visibleRule"].as_str().map(str::trim).filter(|r| !r.is_empty());
+
+    // Mirrors the docs' phrasing, e.g. "Use when command = install. Verbose logging"
+    let description = match visible_rule {
+        Some(rule) => format!("Use when {}. {}", rule, label),
+        None => label.to_string(),
+    };
+
+    // Inputs hidden by a visible rule are only required while visible
+    let required_status = match (input["required"].as_bool().unwrap_or(false), visible_rule) {
+        (true, Some(rule)) => format!("Required when {}", rule),
+        (true, None) => "Required".to_string(),
+        (false, _) => "Optional".to_string(),
+    };
+
+    let default_value_str = match &input["defaultValue"] {
+        Value::String(value) if !value.is_empty() => Some(value.clone()),
+        Value::Bool(value) => Some(value.to_string()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    };
+
+    // Picklists marked editable accept values outside their options, so stay strings
+    let editable = input["properties"]["EditableOptions"].as_str()
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"));
+    let options = input["options"].as_object()
+        .filter(|options| !options.is_empty() && !editable);
+    let labels: Vec<String> = options.iter()
+        .flat_map(|options| options.values())
+        .map(|label| label.as_str().unwrap_or("").trim().to_string())
+        .collect();
+    let options: Option<Vec<String>> = options.map(|options| options.keys().cloned().collect());
+
+    let (base_csharp_type, enum_options) = match input_type {
+        "boolean" => ("bool".to_string(), None),
+        "int" | "integer" => ("int".to_string(), None),
+        "pickList" | "radio" if options.is_some() => (csharp_identifier(yaml_name), options),
+        _ => ("string".to_string(), None), // string, multiLine, filePath, secureFile, connectedService:*, ...
+    };
+
+    let mut parameter = build_parameter(yaml_name, description, base_csharp_type, enum_options, &required_status, default_value_str);
+    parameter.input_kind = InputKind::from_type(input_type);
+    parameter.visible_when = visible_rule.map(str::to_string);
+    parameter.help = input["helpMarkDown"].as_str().map(help_markdown_to_xml).filter(|h| !h.is_empty());
+    parameter.aliases = input["aliases"].as_array()
+        .map(|aliases| aliases.iter().filter_map(|a| a.as_str()).map(str::to_string).collect())
+        .unwrap_or_default();
+    if parameter.enum_options.is_some() {
+        parameter.enum_labels = labels;
+    }
+    Some(parameter)
+}
+
+// The display name of the group an input is in, e.g. 'Advanced' for 'advanced'
+fn group_display_name(task: &Value, input: &Value) -> Option<String> {
+    let group_name = input["groupName"].as_str().filter(|g| !g.is_empty())?;
+    let display_name = task["groups"].as_array()
+        .and_then(|groups| groups.iter().find(|g| g["name"].as_str() == Some(group_name)))
+        .and_then(|group| group["displayName"].as_str());
+    Some(display_name.unwrap_or(group_name).to_string())
+}
+
+// helpMarkDown as XML doc text: links become <see href>, code spans <c>, and the rest plain text
+pub fn help_markdown_to_xml(markdown: &str) -> String {
+    let text = markdown.split_whitespace().collect::<Vec<_>>().join(" ")
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace("**", "");
+    let text = MARKDOWN_LINK_RE.replace_all(&text, r#"<see href="$Url">$Text</see>"#);
+    MARKDOWN_CODE_RE.replace_all(&text, "<c>$Code</c>").trim().to_string()
+}
+
+// task.json in the azure-pipelines-tasks GitHub repo is usually linked by its 'blob' page;
+// fetch the raw file instead
+pub fn raw_task_json_url(location: &str) -> String {
+    match location.strip_prefix("https://github.com/") {
+        Some(path) if path.contains("/blob/") => {
+            format!("https://raw.githubusercontent.com/{}", path.replacen("/blob/", "/", 1))
+        }
+        _ => location.to_string(),
+    }
+}
+
+// task.json of every task folder under Tasks/ in a local clone of azure-pipelines-tasks,
+// optionally only the folders for one major version
+pub fn repo_task_jsons(repo: &str, version: Option<u32>) -> std::io::Result<Vec<String>> {
+    let mut locations: Vec<String> = std::fs::read_dir(std::path::Path::new(repo).join("Tasks"))?
+        .filter_map(Result::ok)
+        .filter(|folder| match version {
+            Some(version) => folder_version(&folder.file_name().to_string_lossy()) == Some(version),
+            None => true,
+        })
+        .map(|folder| folder.path().join("task.json"))
+        .filter(|task_json| task_json.is_file()) // Skips Common/ and other non-task folders
+        .map(|task_json| task_json.display().to_string())
+        .collect();
+    locations.sort();
+    Ok(locations)
+}
+
+// The major version a task folder is for, e.g. 12 for 'FooV12' (which isn't for version 2)
+fn folder_version(folder: &str) -> Option<u32> {
+    let (_, version) = folder.rsplit_once('V')?;
+    version.chars().all(|c| c.is_ascii_digit()).then(|| version.parse().ok()).flatten()
+}
+
+// Points a task.json location at the folder for the given major version
+pub fn versioned_location(location: &str, version: u32) -> String {
+    FOLDER_VERSION_RE.replace(location, format!("V{}${{Suffix}}", version)).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_path_inputs_are_string_backed() {
+        let input = parse_input(&serde_json::json!({
+            "name": "workingDir", "type": "filePath", "label": "Working folder that contains package.json", "required": false
+        })).unwrap();
+        assert_eq!(input.input_kind, InputKind::FilePath);
+        assert_eq!((input.base_csharp_type.as_str(), input.csharp_type.as_str()), ("string", "string?"));
+
+        let input = parse_input(&serde_json::json!({ "name": "customCommand", "type": "string", "label": "Command and arguments" })).unwrap();
+        assert_eq!(input.input_kind, InputKind::Plain);
+    }
+
+    #[test]
+    fn picklist_labels_follow_their_options() {
+        let input = parse_input(&serde_json::json!({
+            "name": "command", "type": "pickList", "label": "Command",
+            "options": { "ci": "ci", "install": "install", "custom": "Run a custom command" }
+        })).unwrap();
+        assert_eq!(input.enum_options.as_deref(), Some(&["ci".to_string(), "install".to_string(), "custom".to_string()][..]));
+        assert_eq!(input.enum_labels, ["ci", "install", "Run a custom command"]);
+
+        // Editable picklists take other values too, so have neither options nor labels
+        let input = parse_input(&serde_json::json!({
+            "name": "versionSpec", "type": "pickList", "label": "Version",
+            "options": { "latest": "Latest" }, "properties": { "EditableOptions": "True" }
+        })).unwrap();
+        assert!(input.enum_options.is_none() && input.enum_labels.is_empty());
+    }
+
+    #[test]
+    fn output_variables_are_read_with_their_descriptions() {
+        let task = parse_task_json(r#"{
+            "name": "NuGetToolInstaller", "version": { "Major": 1 },
+            "outputVariables": [
+                { "name": "NuGetToolPath", "description": " The path to nuget.exe. " },
+                { "name": "NuGetVersion" },
+                { "description": "No name, so skipped" }
+            ]
+        }"#).unwrap();
+        let variables: Vec<(&str, &str)> = task.output_variables.iter().map(|v| (v.name.as_str(), v.description.as_str())).collect();
+        assert_eq!(variables, [("NuGetToolPath", "The path to nuget.exe."), ("NuGetVersion", "")]);
+    }
+}
@@ -0,0 +1,298 @@
+// --- Code Templates ---
+// Files, classes, enums and properties are rendered from Tera templates, with the parsed task as
+// context. The file template lays out everything around the generated code: the header, the
+// usings, '#nullable' and the namespace. The templates come in named sets: the built-in 'sharpliner' set gives the default
+// output and 'minimal' leaves out the XML documentation, while --template-set NAME also finds
+// custom sets in a 'templates/NAME/' directory of the project or of the user's config directory.
+// A set provides any of 'file.cs.tera', 'class.cs.tera', 'enum.cs.tera' and 'property.cs.tera'; those it doesn't
+// provide fall back to the built-in set of the same name, or to 'sharpliner'. --template replaces
+// the class template of whichever set is used.
+// Pieces the generator already knows how to render (summaries, attributes, accessors) are passed
+// pre-rendered alongside the model, so a template only has to rewrite what it wants to change.
+// The 'strip_docs' filter takes the '///' documentation out of such a piece, as 'minimal' does.
+// Model values are passed as they are, so templates escape them for where they put them: the
+// 'csharp_string' filter makes a C# string literal of a value, and Tera's 'escape_xml' escapes it
+// for XML documentation.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tera::{Context, Tera};
+
+use crate::errors::{Failure, FailureKind};
+
+const TEMPLATE_NAMES: [&str; 4] = ["file", "class", "enum", "property"];
+
+// File, class, enum and property templates of each built-in set
+const BUILTIN_SETS: [(&str, [&str; 4]); 2] = [
+    ("sharpliner", [
+        include_str!("templates/sharpliner/file.cs.tera"),
+        include_str!("templates/sharpliner/class.cs.tera"),
+        include_str!("templates/sharpliner/enum.cs.tera"),
+        include_str!("templates/sharpliner/property.cs.tera"),
+    ]),
+    ("minimal", [
+        include_str!("templates/sharpliner/file.cs.tera"),
+        include_str!("templates/minimal/class.cs.tera"),
+        include_str!("templates/minimal/enum.cs.tera"),
+        include_str!("templates/minimal/property.cs.tera"),
+    ]),
+];
+
+pub struct CodeTemplates {
+    tera: Tera,
+}
+
+// What a file template is rendered with: the code of one output file, and what it was generated from
+#[derive(Serialize)]
+pub struct FileContext<'a> {
+    pub kind: &'static str, // 'task', 'tasks' (merged), 'enum', 'enums' (shared) or 'catalog'
+    pub tool_name: &'static str,
+    pub tool_version: &'static str,
+    pub generated_at: Option<String>, // With --timestamp
+    pub sources: Vec<SourceContext<'a>>, // The tasks the code was generated from, by class name
+    pub used_by: Vec<&'a str>, // The classes using the enums of an 'enum' or 'enums' file
+    pub nullable: bool,
+    pub usings: Vec<&'a str>, // Sorted and de-duplicated
+    pub namespace: Option<&'a str>,
+    pub block_namespace: bool,
+    pub body: String, // The enums and classes, without a trailing newline
+}
+
+#[derive(Serialize)]
+pub struct SourceContext<'a> {
+    pub name: String, // 'Name vVersion', or just the name of sources without a version
+    pub task_name: &'a str,
+    pub task_version: &'a str,
+    pub class_name: &'a str,
+    pub documentation_url: &'a str,
+}
+
+// What a class template is rendered with
+#[derive(Serialize)]
+pub struct ClassContext<'a> {
+    pub task_name: &'a str,
+    pub task_version: &'a str,
+    pub reference: &'a str, // What the base constructor is given, e.g. 'Npm@1' or an action's 'uses:'
+    pub class_name: &'a str,
+    pub base_class: &'a str,
+    pub documentation_url: &'a str,
+    pub description: String, // The task's summary, XML-escaped
+    pub partial: bool,
+    pub obsolete_message: Option<String>, // Set for deprecated tasks, escaped for a C# string
+    pub remarks: Vec<String>, // Already formatted as XML doc text
+    pub examples: &'a [String],
+    pub task_id: Option<&'a str>,
+    pub default_display_name: Option<&'a str>, // e.g. "dotnet $(command)"
+    pub demands: &'a [String],
+    pub minimum_agent_version: Option<&'a str>,
+    pub properties: Vec<PropertyContext<'a>>,
+    pub outputs: Vec<OutputContext<'a>>,
+    // Pre-rendered
+    pub summary: String,    // The '///' lines of the class summary
+    pub attributes: String, // Remarks, examples and attributes, each line ending in a newline
+    pub constants: String,  // TaskId, DefaultDisplayName, Demands and MinimumAgentVersion
+    pub members: String,    // Properties and the Outputs class
+}
+
+// What a property template is rendered with; class templates get these too, with their 'code'
+#[derive(Serialize)]
+pub struct PropertyContext<'a> {
+    pub yaml_name: &'a str,
+    pub name: &'a str,
+    #[serde(rename = "type")]
+    pub type_name: String, // As declared, e.g. 'string?' or the --file-path-type
+    pub description: &'a str,
+    pub nullable: bool,
+    pub required: bool, // Always; see required_when for inputs required under a condition
+    pub kind: &'static str, // 'string', 'filePath', 'secureFile' or 'connection'
+    pub default: Option<&'a str>, // As a C# expression
+    pub example: Option<&'a str>, // The value the documentation's snippet shows
+    pub required_when: Option<&'a str>,
+    pub visible_when: Option<&'a str>,
+    pub options: Option<&'a [String]>,
+    pub labels: &'a [String], // Display labels of the options, when known
+    pub aliases: &'a [String], // Other YAML names of the input
+    pub group: Option<&'a str>,
+    // Pre-rendered
+    pub docs: String,       // The '///' lines of the summary and remarks
+    pub attributes: String, // Each on its own line, including [YamlIgnore]
+    pub getter: String,     // The getter's expression
+    pub value: String,      // What the setter passes to SetProperty
+    pub code: String,       // The rendered property, once it has been
+}
+
+#[derive(Serialize)]
+pub struct OutputContext<'a> {
+    pub name: &'a str,
+    pub description: &'a str,
+}
+
+// What an enum template is rendered with
+#[derive(Serialize)]
+pub struct EnumContext<'a> {
+    pub name: &'a str,
+    pub yaml_name: &'a str, // Of the input the enum is the type of
+    pub options: Vec<EnumOptionContext>,
+}
+
+#[derive(Serialize)]
+pub struct EnumOptionContext {
+    pub name: String,
+    pub alias: String,         // The value written to YAML
+    pub label: Option<String>, // The display label, XML-escaped, when it differs from the value
+}
+
+impl CodeTemplates {
+    // The default set, without overrides
+    #[cfg(test)]
+    pub fn builtin() -> Self {
+        let tera = Self::builtin_set("sharpliner").expect("Missing built-in template set");
+        CodeTemplates { tera: tera.expect("Invalid built-in templates") }
+    }
+
+    // The default set with one of its templates replaced
+    #[cfg(test)]
+    pub fn with_template(mut self, name: &str, source: &str) -> Self {
+        self.tera.add_raw_template(name, source).expect("Invalid template");
+        self
+    }
+
+    // The templates of --template-set, if given, with the class template of --template, if given
+    pub fn load(template: Option<&Path>, set: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+        let set_dir = set.and_then(find_set_dir);
+        let builtin = set.and_then(Self::builtin_set);
+        if let Some(set) = set
+            && builtin.is_none()
+            && set_dir.is_none()
+        {
+            return Err(Failure::new(FailureKind::Usage, format!(
+                "Template set '{}' not found; the built-in sets are {}, and custom sets are looked for in {}",
+                set,
+                BUILTIN_SETS.iter().map(|(name, _)| format!("'{}'", name)).collect::<Vec<_>>().join(" and "),
+                set_dirs().iter().map(|d| d.display().to_string()).collect::<Vec<_>>().join(" and "),
+            )).into());
+        }
+        let mut tera = builtin
+            .unwrap_or_else(|| Self::builtin_set("sharpliner").expect("Missing built-in template set"))
+            .map_err(|e| format!("Invalid built-in templates: {}", error_chain(&e)))?;
+
+        let overrides = TEMPLATE_NAMES.iter()
+            .filter_map(|name| set_dir.as_ref().map(|dir| (*name, dir.join(format!("{}.cs.tera", name)))))
+            .filter(|(_, path)| path.is_file())
+            .chain(template.map(|path| ("class", path.to_path_buf())));
+        for (name, path) in overrides {
+            crate::print_diagnostic(&format!("// Using the {} template {}", name, path.display()));
+            let source = std::fs::read_to_string(&path)
+                .map_err(|e| Failure::new(FailureKind::Usage, format!("Failed to read template {}: {}", path.display(), e)))?;
+            tera.add_raw_template(name, &source)
+                .map_err(|e| Failure::new(FailureKind::Usage, format!("Invalid template {}: {}", path.display(), error_chain(&e))))?;
+        }
+        Ok(CodeTemplates { tera })
+    }
+
+    fn builtin_set(name: &str) -> Option<Result<Tera, tera::Error>> {
+        let (_, sources) = BUILTIN_SETS.iter().find(|(set, _)| *set == name)?;
+        let mut tera = Tera::default();
+        tera.autoescape_on(Vec::new()); // C#, not HTML
+        tera.register_filter("strip_docs", strip_docs);
+        tera.register_filter("csharp_string", csharp_string);
+        Some(tera.add_raw_templates(TEMPLATE_NAMES.into_iter().zip(sources.iter().copied())).map(|_| tera))
+    }
+
+    // Files end in exactly one newline
+    pub fn render_file(&self, file: &FileContext) -> Result<String, Failure> {
+        self.render("file", file, file.kind).map(|code| format!("{}\n", code.trim_end()))
+    }
+
+    pub fn render_class(&self, class: &ClassContext) -> Result<String, Failure> {
+        // The output assembly relies on each class ending in exactly one newline
+        self.render("class", class, class.class_name).map(|code| format!("{}\n", code.trim_end()))
+    }
+
+    pub fn render_enum(&self, e: &EnumContext) -> Result<String, Failure> {
+        self.render("enum", e, e.name).map(|code| code.trim_end().to_string())
+    }
+
+    // Properties are separated by a blank line
+    pub fn render_property(&self, property: &PropertyContext) -> Result<String, Failure> {
+        self.render("property", property, property.name).map(|code| format!("{}\n\n", code.trim_end()))
+    }
+
+    fn render(&self, template: &str, context: &impl Serialize, name: &str) -> Result<String, Failure> {
+        Context::from_serialize(context)
+            .and_then(|context| self.tera.render(template, &context))
+            .map_err(|e| Failure::new(
+                FailureKind::Emission,
+                format!("Failed to render the {} template for {}: {}", template, name, error_chain(&e)),
+            ))
+    }
+}
+
+// Removes the '///' lines from pre-rendered code, e.g. '{{ members | strip_docs }}'
+fn strip_docs(value: &tera::Value, _: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+    let code = value.as_str().ok_or_else(|| tera::Error::msg("strip_docs only applies to strings"))?;
+    Ok(tera::Value::String(
+        code.split_inclusive('\n').filter(|line| !line.trim_start().starts_with("///")).collect()
+    ))
+}
+
+// A C# string literal of a model value, e.g. '{{ option.alias | csharp_string }}'
+fn csharp_string(value: &tera::Value, _: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+    let value = value.as_str().ok_or_else(|| tera::Error::msg("csharp_string only applies to strings"))?;
+    Ok(tera::Value::String(crate::csharp_string_literal(value)))
+}
+
+// Where custom template sets are looked for, in order: the project's 'templates' directory, then
+// the user's
+fn set_dirs() -> Vec<PathBuf> {
+    let user_config_dir = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+    };
+    std::iter::once(PathBuf::from("templates"))
+        .chain(user_config_dir.map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("templates")))
+        .collect()
+}
+
+fn find_set_dir(set: &str) -> Option<PathBuf> {
+    set_dirs().into_iter().map(|dir| dir.join(set)).find(|dir| dir.is_dir())
+}
+
+// Tera keeps the useful part of an error (which variable, which line) in its sources
+fn error_chain(error: &tera::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_values_are_escaped_by_the_templates() {
+        let templates = CodeTemplates::builtin();
+        let code = templates.render_enum(&EnumContext {
+            name: "Mode",
+            yaml_name: "mode",
+            options: vec![EnumOptionContext { name: "Quoted".to_string(), alias: "say \"hi\"".to_string(), label: None }],
+        }).unwrap();
+        assert!(code.contains("    [YamlMember(Alias = \"say \\\"hi\\\"\")]\n    Quoted,\n"));
+
+        let templates = templates.with_template("enum", "{{ options.0.alias | csharp_string }} {{ yaml_name | escape_xml }}");
+        let code = templates.render_enum(&EnumContext {
+            name: "Mode",
+            yaml_name: "<mode>",
+            options: vec![EnumOptionContext { name: "Path".to_string(), alias: "C:\\temp".to_string(), label: None }],
+        }).unwrap();
+        assert_eq!(code, "@\"C:\\temp\" &lt;mode&gt;");
+    }
+}
@@ -0,0 +1,37 @@
+// --- Internet Archive Fallback ---
+// Docs pages of retired or renamed tasks disappear from learn.microsoft.com, but the Wayback
+// Machine usually still has them. Its availability API finds the latest snapshot of a page.
+
+use serde_json::Value;
+
+const AVAILABILITY_API_URL: &str = "https://archive.org/wayback/available?url=";
+
+// URL of the availability API query for a page
+pub fn availability_url(page_url: &str) -> String {
+    format!("{}{}", AVAILABILITY_API_URL, percent_encode(page_url))
+}
+
+// The latest snapshot in an availability API response, as its raw page URL and timestamp
+pub fn latest_snapshot(availability_json: &str) -> Option<(String, String)> {
+    let response: Value = serde_json::from_str(availability_json).ok()?;
+    let closest = &response["archived_snapshots"]["closest"];
+    if closest["available"].as_bool() != Some(true) {
+        return None;
+    }
+
+    let timestamp = closest["timestamp"].as_str()?.to_string();
+    let url = closest["url"].as_str()?;
+    // The 'id_' flag serves the page as it was archived, without the Wayback Machine's toolbar
+    // and rewritten links
+    let raw_url = url.replacen(&format!("/{}/", timestamp), &format!("/{}id_/", timestamp), 1);
+    Some((raw_url, timestamp))
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}